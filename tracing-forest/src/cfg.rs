@@ -45,3 +45,15 @@ macro_rules! cfg_chrono {
         )*
     }
 }
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! cfg_json {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "json")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+            $item
+        )*
+    }
+}
@@ -2,6 +2,8 @@ pub const SPAN_NOT_IN_CONTEXT: &str = "Span not in context, this is a bug";
 pub const OPENED_SPAN_NOT_IN_EXTENSIONS: &str =
     "Span extension doesn't contain `OpenedSpan`, this is a bug";
 pub const PROCESSING_ERROR: &str = "Processing logs failed";
+pub const FLUSHING_ERROR: &str = "Flushing logs failed";
+pub const SHUTDOWN_ERROR: &str = "Shutting down the processor failed";
 
 cfg_uuid! {
     pub const NO_CURRENT_SPAN: &str = "The subscriber isn't in any spans";
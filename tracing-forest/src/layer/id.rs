@@ -45,6 +45,7 @@ pub fn id() -> Uuid {
             .extensions()
             .get::<OpenedSpan>()
             .expect(fail::NO_FOREST_LAYER)
+            .state()
             .uuid()
     })
 }
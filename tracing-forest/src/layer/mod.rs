@@ -1,12 +1,18 @@
 use crate::fail;
+use crate::panic::{self, OpenSpans};
+#[cfg(feature = "chrono")]
+use crate::printer::TimestampMode;
 use crate::printer::{PrettyPrinter, TestCapturePrinter};
 use crate::processor::{Processor, Sink};
 use crate::tag::{NoTag, Tag, TagParser};
 use crate::tree::{self, FieldSet, Tree};
 #[cfg(feature = "chrono")]
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::any::TypeId;
 use std::fmt;
 use std::io::{self, Write};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError, Weak};
 use std::time::Instant;
 use tracing::field::{Field, Visit};
 use tracing::span::{Attributes, Id, Record};
@@ -20,9 +26,90 @@ use uuid::Uuid;
 #[cfg(feature = "uuid")]
 pub(crate) mod id;
 
-pub(crate) struct OpenedSpan {
+/// The state of an open span, stored in its extensions.
+///
+/// Once a panic hook is installed, new spans keep their state behind a lock
+/// of their own, so that the hook can read it through a [`SpanHandle`]
+/// without locking the extensions, which the panicking thread may already
+/// hold. Otherwise, the state is stored as it is.
+// The extensions box the value anyway, so boxing the state would only add an
+// allocation.
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum OpenedSpan {
+    Local(SpanState),
+    Shared(Arc<Mutex<SpanState>>),
+}
+
+pub(crate) struct SpanState {
     span: tree::Span,
     start: Instant,
+    /// When the root span of this span's tree was opened.
+    #[cfg(feature = "timeline")]
+    origin: Instant,
+}
+
+/// A handle to an [`OpenedSpan`] that doesn't keep it alive.
+pub(crate) struct SpanHandle(Weak<Mutex<SpanState>>);
+
+impl SpanHandle {
+    /// Returns a copy of the span as it has been recorded so far, or `None` if
+    /// it was closed or is locked.
+    pub(crate) fn try_snapshot(&self) -> Option<tree::Span> {
+        let state = self.0.upgrade()?;
+        let snapshot = match state.try_lock() {
+            Ok(state) => Some(state.span.clone()),
+            Err(TryLockError::Poisoned(err)) => Some(err.into_inner().span.clone()),
+            Err(TryLockError::WouldBlock) => None,
+        };
+        snapshot
+    }
+}
+
+/// Read access to the [`SpanState`] of an [`OpenedSpan`].
+#[cfg_attr(
+    not(any(feature = "uuid", feature = "chrono", feature = "timeline")),
+    allow(dead_code)
+)]
+pub(crate) enum StateRef<'a> {
+    Local(&'a SpanState),
+    Shared(MutexGuard<'a, SpanState>),
+}
+
+/// Write access to the [`SpanState`] of an [`OpenedSpan`].
+pub(crate) enum StateMut<'a> {
+    Local(&'a mut SpanState),
+    Shared(MutexGuard<'a, SpanState>),
+}
+
+impl Deref for StateRef<'_> {
+    type Target = SpanState;
+
+    fn deref(&self) -> &SpanState {
+        match self {
+            StateRef::Local(state) => state,
+            StateRef::Shared(state) => state,
+        }
+    }
+}
+
+impl Deref for StateMut<'_> {
+    type Target = SpanState;
+
+    fn deref(&self) -> &SpanState {
+        match self {
+            StateMut::Local(state) => state,
+            StateMut::Shared(state) => state,
+        }
+    }
+}
+
+impl DerefMut for StateMut<'_> {
+    fn deref_mut(&mut self) -> &mut SpanState {
+        match self {
+            StateMut::Local(state) => state,
+            StateMut::Shared(state) => state,
+        }
+    }
 }
 
 impl OpenedSpan {
@@ -33,7 +120,7 @@ impl OpenedSpan {
         let mut fields = FieldSet::default();
         #[cfg(feature = "uuid")]
         let mut maybe_uuid = None;
-        #[cfg(feature = "uuid")]
+        #[cfg(feature = "defer")]
         let mut defer = false;
 
         attrs.record(&mut |field: &Field, value: &dyn fmt::Debug| {
@@ -73,7 +160,7 @@ impl OpenedSpan {
                     .extensions()
                     .get::<OpenedSpan>()
                     .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS)
-                    .span
+                    .state()
                     .uuid(),
                 None => Uuid::new_v4(),
             }),
@@ -84,22 +171,114 @@ impl OpenedSpan {
         #[cfg(feature = "defer")]
         let span = span.defer_unless_children_attached(defer);
 
-        OpenedSpan {
+        let start = Instant::now();
+
+        // Intervals are measured from when the root of the tree opened, so
+        // inherit the origin from the parent if there is one.
+        #[cfg(feature = "timeline")]
+        let origin = {
+            let parent = if attrs.is_contextual() {
+                _ctx.lookup_current()
+            } else {
+                attrs.parent().and_then(|id| _ctx.span(id))
+            };
+
+            parent
+                .and_then(|parent| {
+                    let extensions = parent.extensions();
+                    let origin = extensions.get::<OpenedSpan>()?.state().origin;
+                    Some(origin)
+                })
+                .unwrap_or(start)
+        };
+
+        let state = SpanState {
             span,
-            start: Instant::now(),
+            start,
+            #[cfg(feature = "timeline")]
+            origin,
+        };
+
+        if panic::is_installed() {
+            OpenedSpan::Shared(Arc::new(Mutex::new(state)))
+        } else {
+            OpenedSpan::Local(state)
         }
     }
 
+    #[cfg_attr(
+        not(any(feature = "uuid", feature = "chrono", feature = "timeline")),
+        allow(dead_code)
+    )]
+    pub(crate) fn state(&self) -> StateRef<'_> {
+        match self {
+            OpenedSpan::Local(state) => StateRef::Local(state),
+            OpenedSpan::Shared(state) => {
+                StateRef::Shared(state.lock().unwrap_or_else(PoisonError::into_inner))
+            }
+        }
+    }
+
+    fn state_mut(&mut self) -> StateMut<'_> {
+        match self {
+            OpenedSpan::Local(state) => StateMut::Local(state),
+            OpenedSpan::Shared(state) => {
+                StateMut::Shared(state.lock().unwrap_or_else(PoisonError::into_inner))
+            }
+        }
+    }
+
+    /// Returns a handle for the panic hook, if the span was opened after it
+    /// was installed.
+    pub(crate) fn handle(&self) -> Option<SpanHandle> {
+        match self {
+            OpenedSpan::Local(_) => None,
+            OpenedSpan::Shared(state) => Some(SpanHandle(Arc::downgrade(state))),
+        }
+    }
+
+    fn close(self) -> tree::Span {
+        let state = match self {
+            OpenedSpan::Local(state) => return state.span,
+            OpenedSpan::Shared(state) => state,
+        };
+
+        match Arc::try_unwrap(state) {
+            Ok(state) => {
+                state
+                    .into_inner()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .span
+            }
+            // The panic hook is taking a snapshot on another thread.
+            Err(state) => state
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .span
+                .clone(),
+        }
+    }
+}
+
+impl SpanState {
     fn enter(&mut self) {
         self.start = Instant::now();
+
+        #[cfg(feature = "timeline")]
+        {
+            self.span.enter_count += 1;
+        }
     }
 
     fn exit(&mut self) {
-        self.span.total_duration += self.start.elapsed();
-    }
+        let elapsed = self.start.elapsed();
+        self.span.total_duration += elapsed;
 
-    fn close(self) -> tree::Span {
-        self.span
+        #[cfg(feature = "timeline")]
+        self.span.intervals.push(tree::Interval {
+            start: self.start.saturating_duration_since(self.origin),
+            duration: elapsed,
+        });
     }
 
     fn record_event(&mut self, event: tree::Event) {
@@ -126,16 +305,51 @@ impl OpenedSpan {
 
 /// A [`Layer`] that collects and processes trace data while preserving
 /// contextual coherence.
+///
+/// Outside of a [`worker_task`], nothing flushes or shuts down the processor
+/// of a `ForestLayer` that is installed globally, since it's never dropped.
+/// Processors that buffer trees, like [`Batch`], should be wrapped with
+/// [`Processor::flush_guard`] and the guard held until the program exits.
+///
+/// [`worker_task`]: crate::runtime::worker_task
+/// [`Batch`]: crate::processor::batch::Batch
 #[derive(Clone, Debug)]
 pub struct ForestLayer<P, T> {
     processor: P,
     tag: T,
+    #[cfg(feature = "chrono")]
+    immediate_timestamp: TimestampMode,
+    open: OpenSpans,
 }
 
 impl<P: Processor, T: TagParser> ForestLayer<P, T> {
     /// Create a new `ForestLayer` from a [`Processor`] and a [`TagParser`].
     pub fn new(processor: P, tag: T) -> Self {
-        ForestLayer { processor, tag }
+        ForestLayer {
+            processor,
+            tag,
+            #[cfg(feature = "chrono")]
+            immediate_timestamp: TimestampMode::Rfc3339,
+            open: OpenSpans::default(),
+        }
+    }
+
+    /// Set how timestamps are displayed for events marked as `immediate`.
+    ///
+    /// The default is [`TimestampMode::Rfc3339`]. Relative modes are measured
+    /// against the root span and the previous node recorded in the current
+    /// span.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mode` has an invalid [`strftime`] pattern.
+    ///
+    /// [`strftime`]: chrono::format::strftime
+    #[cfg(feature = "chrono")]
+    pub fn immediate_timestamp(mut self, mode: TimestampMode) -> Self {
+        mode.validate();
+        self.immediate_timestamp = mode;
+        self
     }
 }
 
@@ -154,10 +368,7 @@ impl ForestLayer<Sink, NoTag> {
 
 impl Default for ForestLayer<PrettyPrinter, NoTag> {
     fn default() -> Self {
-        ForestLayer {
-            processor: PrettyPrinter::new(),
-            tag: NoTag,
-        }
+        ForestLayer::new(PrettyPrinter::new(), NoTag)
     }
 }
 
@@ -175,42 +386,49 @@ where
             return;
         }
 
+        self.open
+            .insert(id, span.parent().map(|parent| parent.id()), &opened);
+
         let mut extensions = span.extensions_mut();
         extensions.insert(opened);
     }
 
     fn on_record(&self, span: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
-        struct Visitor<'a> {
-            fields: &'a mut FieldSet,
+        struct Visitor {
+            fields: Vec<tree::Field>,
         }
 
-        impl<'a> Visit for Visitor<'a> {
+        impl Visit for Visitor {
             fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
                 let value = format!("{:?}", value);
-                let new_field = tree::Field::new(field.name(), value);
-
-                if let Some(old_field) = self
-                    .fields
-                    .iter_mut()
-                    .find(|old_field| old_field.key() == field.name())
-                {
-                    *old_field = new_field;
-                } else {
-                    self.fields.push(new_field);
-                }
+                self.fields.push(tree::Field::new(field.name(), value));
             }
         }
 
+        // Values are formatted before the extensions are locked, since a
+        // panicking `Debug` impl would otherwise leave them locked while the
+        // panic hook reads them.
+        let mut visitor = Visitor { fields: Vec::new() };
+        values.record(&mut visitor);
+
         let span = ctx.span(span).expect(fail::SPAN_NOT_IN_CONTEXT);
         let mut extensions = span.extensions_mut();
-        let openedspan = extensions
+        let mut opened = extensions
             .get_mut::<OpenedSpan>()
-            .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS);
-
-        let mut visitor = Visitor {
-            fields: &mut openedspan.span.shared.fields,
-        };
-        values.record(&mut visitor);
+            .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS)
+            .state_mut();
+        let fields = &mut opened.span.shared.fields;
+
+        for new_field in visitor.fields {
+            if let Some(old_field) = fields
+                .iter_mut()
+                .find(|old_field| old_field.key() == new_field.key())
+            {
+                *old_field = new_field;
+            } else {
+                fields.push(new_field);
+            }
+        }
     }
 
     fn on_event(&self, event: &Event, ctx: Context<S>) {
@@ -263,7 +481,13 @@ where
         let current_span = ctx.event_span(event);
 
         if visitor.immediate {
-            write_immediate(&tree_event, current_span.as_ref()).expect("writing urgent failed");
+            write_immediate(
+                &tree_event,
+                current_span.as_ref(),
+                #[cfg(feature = "chrono")]
+                &self.immediate_timestamp,
+            )
+            .expect("writing urgent failed");
         }
 
         match current_span.as_ref() {
@@ -271,6 +495,7 @@ where
                 .extensions_mut()
                 .get_mut::<OpenedSpan>()
                 .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS)
+                .state_mut()
                 .record_event(tree_event),
             None => self
                 .processor
@@ -285,6 +510,7 @@ where
             .extensions_mut()
             .get_mut::<OpenedSpan>()
             .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS)
+            .state_mut()
             .enter();
     }
 
@@ -294,10 +520,13 @@ where
             .extensions_mut()
             .get_mut::<OpenedSpan>()
             .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS)
+            .state_mut()
             .exit();
     }
 
     fn on_close(&self, id: Id, ctx: Context<S>) {
+        self.open.remove(&id);
+
         let span_ref = ctx.span(&id).expect(fail::SPAN_NOT_IN_CONTEXT);
 
         let mut span = span_ref
@@ -322,6 +551,7 @@ where
                 .extensions_mut()
                 .get_mut::<OpenedSpan>()
                 .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS)
+                .state_mut()
                 .record_span(span),
             None => self
                 .processor
@@ -329,9 +559,24 @@ where
                 .expect(fail::PROCESSING_ERROR),
         }
     }
+
+    // Exposes the open spans to the panic hook, which only has the subscriber.
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else if id == TypeId::of::<OpenSpans>() {
+            Some(&self.open as *const OpenSpans as *const ())
+        } else {
+            None
+        }
+    }
 }
 
-fn write_immediate<S>(event: &tree::Event, current: Option<&SpanRef<S>>) -> io::Result<()>
+fn write_immediate<S>(
+    event: &tree::Event,
+    current: Option<&SpanRef<S>>,
+    #[cfg(feature = "chrono")] timestamp_mode: &TimestampMode,
+) -> io::Result<()>
 where
     S: for<'a> LookupSpan<'a>,
 {
@@ -347,13 +592,35 @@ where
             .extensions()
             .get::<OpenedSpan>()
             .expect(fail::OPENED_SPAN_NOT_IN_EXTENSIONS)
-            .span
+            .state()
             .uuid();
         write!(writer, "{uuid} ")?;
     }
 
     #[cfg(feature = "chrono")]
-    write!(writer, "{} ", event.timestamp().to_rfc3339())?;
+    {
+        let previous_of = |span: &SpanRef<S>| -> Option<DateTime<Utc>> {
+            let extensions = span.extensions();
+            let opened = extensions.get::<OpenedSpan>()?.state();
+            let previous = match opened.span.nodes.last() {
+                Some(Tree::Event(event)) => event.timestamp(),
+                Some(Tree::Span(span)) => span.timestamp(),
+                None => opened.span.timestamp(),
+            };
+            Some(previous)
+        };
+
+        let root = current.and_then(|span| {
+            let root = span.scope().from_root().next()?;
+            let extensions = root.extensions();
+            let timestamp = extensions.get::<OpenedSpan>()?.state().span.timestamp();
+            Some(timestamp)
+        });
+        let previous = current.and_then(previous_of);
+
+        let timestamp = timestamp_mode.format(event.timestamp(), root, previous);
+        write!(writer, "{timestamp} ")?;
+    }
 
     write!(writer, "{:<8} ", event.level())?;
 
@@ -252,18 +252,28 @@
 //! * `full`: Enables all features listed below.
 //! * `uuid`: Enables spans to carry operation IDs.
 //! * `chrono`: Enables timestamps on trace data.
-//! * `ansi`: Enables ANSI terminal colors.
+//! * `ansi`: Enables ANSI terminal colors when [writing to a terminal][color_mode].
 //! * `smallvec`: Enables some performance optimizations.
 //! * `tokio`: Enables [`worker_task`] and [`capture`].
-//! * `serde`: Enables log trees to be serialized, which is [useful for formatting][serde_fmt].
+//! * `serde`: Enables log trees to be serialized, which is [useful for formatting][serde_fmt], and deserialized.
 //! * `env-filter`: Re-exports [`EnvFilter`] from the [`util`] module.
 //! * `defer`: Allows marking a span with `defer = true` so that it will not be displayed unless it has child nodes.
+//! * `timeline`: Records every interval during which a span was entered, which is [useful for timelines][timeline].
+//! * `json`: Enables the [`Json`] formatter, flat JSON lines with [`Flat`], and [streaming log trees][socket] as JSON over sockets.
+//!   Together with `chrono`, it also enables exporting [Chrome traces][chrome], and with `uuid` as well, [OTLP/JSON][otlp].
 //!
 //! By default, only `smallvec` in enabled.
 //!
 //! [`Uuid`]: uuid::Uuid
 //! [serde_fmt]: crate::printer::Formatter#examples
 //! [`EnvFilter`]: tracing_subscriber::EnvFilter
+//! [timeline]: crate::tree::Span::intervals
+//! [socket]: crate::processor::socket
+//! [`Json`]: crate::printer::Json
+//! [`Flat`]: crate::printer::Flat
+//! [chrome]: crate::printer::ChromeTrace
+//! [otlp]: crate::printer::Otlp
+//! [color_mode]: crate::printer::ColorMode
 
 #![doc(issue_tracker_base_url = "https://github.com/QnnOkabayashi/tracing-forest/issues")]
 #![cfg_attr(
@@ -279,6 +289,7 @@
 #![warn(unused_extern_crates)]
 #![warn(missing_docs)]
 
+pub mod panic;
 pub mod printer;
 pub mod processor;
 pub mod tag;
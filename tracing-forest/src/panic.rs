@@ -0,0 +1,319 @@
+//! Report in-flight trace trees when the process panics.
+//!
+//! See [`hook`] for more details.
+use crate::layer::{OpenedSpan, SpanHandle};
+use crate::printer::{MakeStderr, Printer};
+use crate::processor::{ring, Processor};
+use crate::tree::{self, FieldSet, Tree};
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError, TryLockError};
+use std::thread;
+use tracing::span::Id;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/// Whether a hook has been installed, and open spans need to be tracked.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Returns whether a hook has been installed.
+pub(crate) fn is_installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+const SHARDS: usize = 16;
+
+/// Open spans by ID, along with the IDs of their parents.
+type Shard = HashMap<u64, (Option<u64>, SpanHandle)>;
+
+/// The spans that a [`ForestLayer`] opened since a hook was installed, and
+/// that are still open, along with the IDs of their parents.
+///
+/// Each layer tracks its own spans, since span IDs are only unique within a
+/// subscriber. The hook finds the spans of the panicking thread's subscriber
+/// by downcasting it. Spans are sharded so that spans opening and closing on
+/// different threads rarely wait on each other.
+///
+/// The hook reads the spans through their [`SpanHandle`]s instead of the
+/// registry's extensions, since the panicking thread may hold the extensions
+/// lock of a span it's in.
+///
+/// [`ForestLayer`]: crate::ForestLayer
+#[derive(Default)]
+pub(crate) struct OpenSpans {
+    shards: [Mutex<Shard>; SHARDS],
+}
+
+impl OpenSpans {
+    pub(crate) fn insert(&self, id: &Id, parent: Option<Id>, opened: &OpenedSpan) {
+        if let Some(handle) = opened.handle() {
+            let parent = parent.map(|parent| parent.into_u64());
+            self.shard(id.into_u64())
+                .insert(id.into_u64(), (parent, handle));
+        }
+    }
+
+    pub(crate) fn remove(&self, id: &Id) {
+        if is_installed() {
+            self.shard(id.into_u64()).remove(&id.into_u64());
+        }
+    }
+
+    fn shard(&self, id: u64) -> MutexGuard<'_, Shard> {
+        self.shards[id as usize % SHARDS]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns a snapshot of each tracked span and the ID of its parent, in
+    /// ascending order of ID.
+    ///
+    /// Shards and spans that are locked, such as by the panicking thread, are
+    /// skipped instead of waited on.
+    fn snapshots(&self) -> Vec<(u64, Option<u64>, tree::Span)> {
+        let mut snapshots = Vec::new();
+
+        for shard in &self.shards {
+            let shard = match shard.try_lock() {
+                Ok(shard) => shard,
+                Err(TryLockError::Poisoned(err)) => err.into_inner(),
+                Err(TryLockError::WouldBlock) => continue,
+            };
+
+            for (id, (parent, handle)) in shard.iter() {
+                if let Some(span) = handle.try_snapshot() {
+                    snapshots.push((*id, *parent, span));
+                }
+            }
+        }
+
+        snapshots.sort_unstable_by_key(|(id, _, _)| *id);
+        snapshots
+    }
+}
+
+impl fmt::Debug for OpenSpans {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenSpans").finish_non_exhaustive()
+    }
+}
+
+/// A cloned layer is a different layer, and tracks its own spans.
+impl Clone for OpenSpans {
+    fn clone(&self) -> Self {
+        OpenSpans::default()
+    }
+}
+
+/// Returns a [`Builder`] for a panic hook that reports what the subscriber
+/// was doing when the panic occurred.
+///
+/// When a thread panics, the hook processes, in order:
+/// 1. Every `Tree` held by the configured [`RingBuffer`], if any.
+/// 2. A snapshot of every span that is still open in the panicking thread's
+///    subscriber, nested as it would be once closed.
+/// 3. An `ERROR` event with the panic message, and the path of spans the
+///    panicking thread was in as its `span` field.
+///
+/// The trees are pretty-printed to stderr by default, after which the
+/// previously installed panic hook runs.
+///
+/// # Note
+///
+/// Only spans opened after the hook is installed are reported, so it should be
+/// installed as early as possible. The hook also requires the subscriber to be
+/// a [`Registry`] composed with a [`ForestLayer`], which is the same
+/// requirement as [`id`].
+///
+/// The hook never waits on a lock, so that a panic can't deadlock it. A span
+/// that is being recorded to when the panic occurs is left out of the report,
+/// and its open children are reported as roots instead.
+/// A panic inside one of the subscriber's own callbacks, such as a panicking
+/// `Layer`, can't reach the subscriber at all, so only the ring buffer and the
+/// panic message are reported.
+///
+/// Trees that were already sent to a [`worker_task`] but not yet processed
+/// aren't reported. They're still processed by the worker task if it keeps
+/// running after the panic.
+///
+/// # Examples
+///
+/// ```
+/// use tracing_forest::processor::ring::RingBuffer;
+/// use tracing_forest::{traits::*, util::*};
+/// use tracing_subscriber::Registry;
+///
+/// let ring = RingBuffer::new(256);
+///
+/// tracing_forest::panic::hook().ring(ring.handle()).install();
+///
+/// Registry::default()
+///     .with(ForestLayer::from(ring))
+///     .init();
+/// ```
+///
+/// [`RingBuffer`]: crate::processor::ring::RingBuffer
+/// [`ForestLayer`]: crate::ForestLayer
+/// [`id`]: crate::id
+/// [`worker_task`]: crate::runtime::worker_task
+pub fn hook() -> Builder {
+    Builder {
+        processor: into_fn(Printer::new().writer(MakeStderr)),
+        ring: None,
+    }
+}
+
+/// Installs a panic hook with the default configuration.
+///
+/// This is shorthand for `hook().install()`. See [`hook`] for more details.
+pub fn install() {
+    hook().install();
+}
+
+/// Configures and installs a panic hook.
+///
+/// This type is created by [`hook`].
+pub struct Builder {
+    processor: Box<dyn Fn(Tree) + Send + Sync>,
+    ring: Option<ring::Handle>,
+}
+
+fn into_fn<P: Processor + Send + Sync>(processor: P) -> Box<dyn Fn(Tree) + Send + Sync> {
+    Box::new(move |tree| {
+        // There's nowhere left to report failures to.
+        let _ = processor.process(tree);
+    })
+}
+
+impl Builder {
+    /// Process reported trees with `processor` instead of pretty-printing them
+    /// to stderr.
+    pub fn processor<P: Processor + Send + Sync>(mut self, processor: P) -> Self {
+        self.processor = into_fn(processor);
+        self
+    }
+
+    /// Also report the trees held by a [`RingBuffer`].
+    ///
+    /// The trees are copied, so the buffer is left untouched.
+    ///
+    /// [`RingBuffer`]: crate::processor::ring::RingBuffer
+    pub fn ring(mut self, handle: ring::Handle) -> Self {
+        self.ring = Some(handle);
+        self
+    }
+
+    /// Install the hook, chaining to the previously installed panic hook.
+    pub fn install(self) {
+        INSTALLED.store(true, Ordering::Relaxed);
+
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let message = match info.location() {
+                Some(location) => format!(
+                    "thread '{}' panicked at {}: {}",
+                    thread::current().name().unwrap_or("<unnamed>"),
+                    location,
+                    payload_str(info.payload()),
+                ),
+                None => format!(
+                    "thread '{}' panicked: {}",
+                    thread::current().name().unwrap_or("<unnamed>"),
+                    payload_str(info.payload()),
+                ),
+            };
+
+            self.report(message);
+            previous(info);
+        }));
+    }
+
+    fn report(&self, message: String) {
+        if let Some(ring) = &self.ring {
+            for tree in ring.snapshot() {
+                (self.processor)(tree);
+            }
+        }
+
+        let path = tracing::dispatcher::get_default(|dispatch| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+
+            if let Some(open) = dispatch.downcast_ref::<OpenSpans>() {
+                for tree in in_flight(open) {
+                    (self.processor)(tree);
+                }
+            }
+
+            current_path(registry)
+        });
+
+        let mut fields = FieldSet::default();
+        if let Some(path) = path {
+            fields.push(tree::Field::new("span", path));
+        }
+
+        let event = tree::Event::synthetic(Level::ERROR, message, fields);
+        (self.processor)(Tree::Event(event));
+    }
+}
+
+fn payload_str(payload: &(dyn Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+/// Returns the names of the spans the current thread is in, from the root.
+///
+/// This only reads the registry's span stack and the spans' metadata, so it
+/// doesn't lock any extensions.
+fn current_path(registry: &Registry) -> Option<String> {
+    let current = registry.current_span();
+    let span = registry.span(current.id()?)?;
+
+    let names: Vec<_> = span.scope().from_root().map(|span| span.name()).collect();
+    Some(names.join(" > "))
+}
+
+/// Returns a snapshot of every tracked open span, with open children nested in
+/// their parents.
+fn in_flight(open: &OpenSpans) -> Vec<Tree> {
+    fn attach(
+        id: u64,
+        mut span: tree::Span,
+        children: &mut BTreeMap<u64, Vec<(u64, tree::Span)>>,
+    ) -> tree::Span {
+        for (child_id, child) in children.remove(&id).unwrap_or_default() {
+            let child = attach(child_id, child, children);
+            span.nodes.push(Tree::Span(child));
+        }
+        span
+    }
+
+    let snapshots = open.snapshots();
+    let ids: Vec<u64> = snapshots.iter().map(|(id, _, _)| *id).collect();
+
+    let mut roots = Vec::new();
+    let mut children: BTreeMap<u64, Vec<(u64, tree::Span)>> = BTreeMap::new();
+
+    for (id, parent, span) in snapshots {
+        match parent {
+            Some(parent) if ids.binary_search(&parent).is_ok() => {
+                children.entry(parent).or_default().push((id, span))
+            }
+            _ => roots.push((id, span)),
+        }
+    }
+
+    roots
+        .into_iter()
+        .map(|(id, span)| Tree::Span(attach(id, span, &mut children)))
+        .collect()
+}
@@ -0,0 +1,189 @@
+use crate::printer::Formatter;
+use crate::tree::{Event, Field, Span, Tree};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Format logs as the [Chrome Trace Event Format], which can be opened in
+/// `chrome://tracing` or [Perfetto].
+///
+/// Spans are written as complete events, and events as instant events, with
+/// their fields as arguments. Each `Tree` is drawn on its own track, since
+/// trees are independent of each other, identified by a hash of its root.
+///
+/// If the `timeline` feature is enabled, a complete event is written for every
+/// interval that a span was entered for, so gaps where a `Future` was waiting
+/// show up in the timeline. Otherwise, a single complete event is written from
+/// when the span opened, for as long as it was entered in total.
+///
+/// The output is a JSON array that is left open so that trees can keep being
+/// appended to it, which both viewers accept. Its opening bracket is the
+/// formatter's [`header`], which a [`Printer`] writes once and a
+/// [`RollingFile`] writes at the start of every file, so each file it rotates
+/// to can be opened on its own.
+///
+/// # Examples
+///
+/// Writing a trace of a program to a file.
+/// ```no_run
+/// use tracing_forest::printer::ChromeTrace;
+/// use tracing_forest::processor::file::RollingFile;
+/// use tracing_forest::{traits::*, util::*};
+/// use tracing_subscriber::Registry;
+///
+/// let processor = RollingFile::new(".", "trace.json").formatter(ChromeTrace::new());
+///
+/// Registry::default()
+///     .with(ForestLayer::from(processor))
+///     .init();
+/// ```
+///
+/// [Chrome Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+/// [Perfetto]: https://ui.perfetto.dev
+/// [`header`]: Formatter::header
+/// [`Printer`]: crate::Printer
+/// [`RollingFile`]: crate::processor::file::RollingFile
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChromeTrace;
+
+impl ChromeTrace {
+    /// Returns a `ChromeTrace` formatter.
+    pub const fn new() -> Self {
+        ChromeTrace
+    }
+}
+
+impl Formatter for ChromeTrace {
+    type Error = serde_json::Error;
+
+    fn fmt(&self, tree: &Tree) -> Result<String, serde_json::Error> {
+        let mut string = String::new();
+        let track = Track {
+            pid: std::process::id(),
+            tid: track_id(tree),
+            root: tree_timestamp(tree),
+        };
+
+        let mut events = Vec::new();
+        track.push_tree(tree, &mut events);
+
+        for event in &events {
+            string.push_str(&serde_json::to_string(event)?);
+            string.push_str(",\n");
+        }
+
+        Ok(string)
+    }
+
+    fn header(&self) -> &'static str {
+        "[\n"
+    }
+}
+
+fn tree_timestamp(tree: &Tree) -> DateTime<Utc> {
+    match tree {
+        Tree::Event(event) => event.timestamp(),
+        Tree::Span(span) => span.timestamp(),
+    }
+}
+
+/// Returns the track of a `Tree`, which is the same however many times it's
+/// formatted. It's kept below 2^32 so that it's exact as a JavaScript number.
+fn track_id(tree: &Tree) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tree_timestamp(tree).hash(&mut hasher);
+    #[cfg(feature = "uuid")]
+    match tree {
+        Tree::Event(event) => event.uuid().hash(&mut hasher),
+        Tree::Span(span) => span.uuid().hash(&mut hasher),
+    }
+    hasher.finish() >> 32
+}
+
+/// Returns microseconds since the Unix epoch, which is what trace events use.
+fn micros(timestamp: DateTime<Utc>) -> f64 {
+    timestamp.timestamp_micros() as f64
+}
+
+fn args(fields: &[Field]) -> Map<String, Value> {
+    fields
+        .iter()
+        .map(|field| (field.key().to_string(), field.value().into()))
+        .collect()
+}
+
+/// Where the trace events of a single `Tree` are drawn.
+struct Track {
+    pid: u32,
+    tid: u64,
+    /// When the root of the tree opened.
+    #[cfg_attr(not(feature = "timeline"), allow(dead_code))]
+    root: DateTime<Utc>,
+}
+
+impl Track {
+    fn push_tree(&self, tree: &Tree, events: &mut Vec<Value>) {
+        match tree {
+            Tree::Event(event) => events.push(self.instant(event)),
+            Tree::Span(span) => {
+                if !span.should_render() {
+                    return;
+                }
+                self.push_span(span, events);
+                for node in span.nodes() {
+                    self.push_tree(node, events);
+                }
+            }
+        }
+    }
+
+    fn instant(&self, event: &Event) -> Value {
+        let mut args = args(event.fields());
+        if let Some(tag) = event.tag() {
+            args.insert("tag".to_string(), tag.to_string().into());
+        }
+
+        json!({
+            "name": event.message().unwrap_or("event"),
+            "cat": event.level().as_str(),
+            "ph": "i",
+            "s": "t",
+            "ts": micros(event.timestamp()),
+            "pid": self.pid,
+            "tid": self.tid,
+            "args": args,
+        })
+    }
+
+    fn complete(&self, span: &Span, start: DateTime<Utc>, duration: std::time::Duration) -> Value {
+        json!({
+            "name": span.name(),
+            "cat": span.level().as_str(),
+            "ph": "X",
+            "ts": micros(start),
+            "dur": duration.as_nanos() as f64 / 1000.0,
+            "pid": self.pid,
+            "tid": self.tid,
+            "args": args(span.fields()),
+        })
+    }
+
+    #[cfg(feature = "timeline")]
+    fn push_span(&self, span: &Span, events: &mut Vec<Value>) {
+        if span.intervals().is_empty() {
+            events.push(self.complete(span, span.timestamp(), span.total_duration()));
+        }
+
+        for interval in span.intervals() {
+            let start = chrono::Duration::from_std(interval.start())
+                .map_or(self.root, |start| self.root + start);
+            events.push(self.complete(span, start, interval.duration()));
+        }
+    }
+
+    #[cfg(not(feature = "timeline"))]
+    fn push_span(&self, span: &Span, events: &mut Vec<Value>) {
+        events.push(self.complete(span, span.timestamp(), span.total_duration()));
+    }
+}
@@ -0,0 +1,66 @@
+use super::MakeTerminal;
+use std::env;
+use std::sync::OnceLock;
+
+/// When output is colored.
+///
+/// Color is only ever written if the `ansi` feature is enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Always write color.
+    Always,
+
+    /// Never write color.
+    Never,
+
+    /// Write color if the output is a terminal.
+    ///
+    /// A [`Printer`] lets environment variables override whether its writer
+    /// is a terminal: setting `NO_COLOR` to a non-empty value disables color,
+    /// and otherwise setting `CLICOLOR_FORCE` to a value other than `0`
+    /// enables it. See <https://no-color.org> and <https://bixense.com/clicolors>.
+    ///
+    /// [`Printer`]: super::Printer
+    #[default]
+    Auto,
+}
+
+impl ColorMode {
+    /// Returns whether to write color to an output that may be a terminal.
+    pub(crate) fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => is_terminal,
+        }
+    }
+}
+
+/// Returns whether to treat the writers made by `make_writer` as a terminal,
+/// unless the environment forces color on or off.
+///
+/// The writer is always asked, so that it's asked the same number of times
+/// regardless of the environment.
+pub(crate) fn treat_as_terminal(make_writer: &impl MakeTerminal) -> bool {
+    let is_terminal = make_writer.is_terminal();
+    env_override().unwrap_or(is_terminal)
+}
+
+/// Returns whether color is forced on or off by the environment.
+///
+/// The environment is only read once, since it's checked for every printer.
+fn env_override() -> Option<bool> {
+    static OVERRIDE: OnceLock<Option<bool>> = OnceLock::new();
+
+    *OVERRIDE.get_or_init(|| {
+        let is_set = |key, off: &str| env::var_os(key).is_some_and(|value| value != off);
+
+        if is_set("NO_COLOR", "") {
+            Some(false)
+        } else if is_set("CLICOLOR_FORCE", "0") {
+            Some(true)
+        } else {
+            None
+        }
+    })
+}
@@ -0,0 +1,289 @@
+use crate::cfg_json;
+use crate::printer::Formatter;
+use crate::tree::{Event, Field, Shared, Span, Tree};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+
+/// Format logs as one line per event, for log aggregators that work line by
+/// line.
+///
+/// Each event is written with its level, the path of spans it occurred in,
+/// its message and tag, and the fields of the event and all of its spans.
+/// Fields of inner spans and of the event override fields of outer spans
+/// with the same key. Fields whose keys are used by the line itself, like
+/// `level` or `msg`, are written with a `field.` prefix, as in `field.level`,
+/// so they can't be mistaken for the line's own values. Timestamps and uuids
+/// are included if the `chrono` and `uuid` features are enabled.
+///
+/// Lines are written as [logfmt] by default, or as flat JSON objects with
+/// [`Flat::json`].
+///
+/// # Examples
+///
+/// ```txt
+/// level=INFO span="server > request" tag=info msg=received path=/login
+/// level=WARN span="server > request > auth" tag=warn msg="bad password" path=/login user="\"admin\""
+/// ```
+/// Values are quoted and escaped whenever they contain spaces, quotes, or
+/// other special characters. String fields recorded with `Debug`, like
+/// `user = "admin"`, keep their quotes, so record them with `Display`, as in
+/// `path = %"/login"`, to write them unquoted.
+/// With [`span_closes`], a line is also written after the events of each span,
+/// with how long it was entered for in nanoseconds:
+/// ```txt
+/// level=INFO span="server > request" msg=close nanos_total=1520000 nanos_nested=310000 path=/login
+/// ```
+///
+/// [logfmt]: https://brandur.org/logfmt
+/// [`span_closes`]: Flat::span_closes
+#[derive(Clone, Copy, Debug)]
+pub struct Flat {
+    style: Style,
+    span_closes: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Style {
+    Logfmt,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// A value on a line.
+enum Value<'a> {
+    Str(Cow<'a, str>),
+    Nanos(u64),
+}
+
+impl Flat {
+    /// Returns a `Flat` formatter that writes logfmt lines.
+    pub const fn logfmt() -> Self {
+        Flat {
+            style: Style::Logfmt,
+            span_closes: false,
+        }
+    }
+
+    /// Write a line when each span closes, after the lines of its events.
+    ///
+    /// The line has the span's timestamp, which is when it opened, and its
+    /// total and nested durations in nanoseconds.
+    pub const fn span_closes(mut self, show: bool) -> Self {
+        self.span_closes = show;
+        self
+    }
+}
+
+cfg_json! {
+    impl Flat {
+        /// Returns a `Flat` formatter that writes lines of JSON objects.
+        ///
+        /// Field values are written as strings, as they are in [`Json`].
+        ///
+        /// [`Json`]: crate::printer::Json
+        pub const fn json() -> Self {
+            Flat {
+                style: Style::Json,
+                span_closes: false,
+            }
+        }
+    }
+}
+
+impl Default for Flat {
+    fn default() -> Self {
+        Flat::logfmt()
+    }
+}
+
+impl Formatter for Flat {
+    type Error = fmt::Error;
+
+    fn fmt(&self, tree: &Tree) -> Result<String, fmt::Error> {
+        let mut writer = String::with_capacity(256);
+        self.format_tree(tree, &mut Vec::new(), &mut Vec::new(), &mut writer)?;
+        Ok(writer)
+    }
+}
+
+impl Flat {
+    fn format_tree<'a>(
+        &self,
+        tree: &'a Tree,
+        path: &mut Vec<&'a str>,
+        fields: &mut Vec<&'a Field>,
+        writer: &mut String,
+    ) -> fmt::Result {
+        match tree {
+            Tree::Event(event) => self.format_event(event, path, fields, writer),
+            Tree::Span(span) => {
+                if !span.should_render() {
+                    return Ok(());
+                }
+
+                path.push(span.name());
+                let outer_fields = fields.len();
+                fields.extend(span.fields());
+
+                for node in span.nodes() {
+                    self.format_tree(node, path, fields, writer)?;
+                }
+
+                if self.span_closes {
+                    self.format_close(span, path, fields, writer)?;
+                }
+
+                fields.truncate(outer_fields);
+                path.pop();
+
+                Ok(())
+            }
+        }
+    }
+
+    fn format_event(
+        &self,
+        event: &Event,
+        path: &[&str],
+        fields: &[&Field],
+        writer: &mut String,
+    ) -> fmt::Result {
+        let mut line = Flat::shared(&event.shared, path);
+
+        if let Some(tag) = event.tag() {
+            line.push(("tag", Value::Str(Cow::Owned(tag.to_string()))));
+        }
+        if let Some(message) = event.message() {
+            line.push(("msg", Value::Str(Cow::Borrowed(message))));
+        }
+
+        let fields = fields.iter().copied().chain(event.fields());
+        self.format_line(line, fields, writer)
+    }
+
+    fn format_close(
+        &self,
+        span: &Span,
+        path: &[&str],
+        fields: &[&Field],
+        writer: &mut String,
+    ) -> fmt::Result {
+        let nanos = |duration: std::time::Duration| {
+            Value::Nanos(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX))
+        };
+
+        let mut line = Flat::shared(&span.shared, path);
+        line.push(("msg", Value::Str(Cow::Borrowed("close"))));
+        line.push(("nanos_total", nanos(span.total_duration())));
+        line.push(("nanos_nested", nanos(span.inner_duration())));
+
+        self.format_line(line, fields.iter().copied(), writer)
+    }
+
+    /// Returns the values that every line starts with.
+    fn shared<'a>(shared: &Shared, path: &[&str]) -> Vec<(&'static str, Value<'a>)> {
+        let mut line = Vec::with_capacity(8);
+
+        #[cfg(feature = "chrono")]
+        line.push(("ts", Value::Str(Cow::Owned(shared.timestamp.to_rfc3339()))));
+
+        line.push(("level", Value::Str(Cow::Borrowed(shared.level.as_str()))));
+
+        #[cfg(feature = "uuid")]
+        line.push(("uuid", Value::Str(Cow::Owned(shared.uuid.to_string()))));
+
+        if !path.is_empty() {
+            line.push(("span", Value::Str(Cow::Owned(path.join(" > ")))));
+        }
+
+        line
+    }
+
+    /// Writes `line` followed by `fields`, where later fields replace earlier
+    /// ones with the same key.
+    fn format_line<'a>(
+        &self,
+        mut line: Vec<(&'a str, Value<'a>)>,
+        fields: impl Iterator<Item = &'a Field>,
+        writer: &mut String,
+    ) -> fmt::Result {
+        for field in fields {
+            let key = field_key(field.key());
+            let value = Value::Str(Cow::Borrowed(field.value()));
+            match line.iter_mut().find(|(existing, _)| *existing == key) {
+                Some((_, existing)) => *existing = value,
+                None => line.push((key, value)),
+            }
+        }
+
+        match self.style {
+            Style::Logfmt => {
+                for (n, (key, value)) in line.iter().enumerate() {
+                    if n > 0 {
+                        writer.write_char(' ')?;
+                    }
+                    write!(writer, "{key}=")?;
+                    match value {
+                        Value::Str(value) => write_logfmt_value(value, writer)?,
+                        Value::Nanos(nanos) => write!(writer, "{nanos}")?,
+                    }
+                }
+            }
+            #[cfg(feature = "json")]
+            Style::Json => {
+                // Written by hand, since `serde_json::Map` would sort the keys.
+                writer.write_char('{')?;
+                for (n, (key, value)) in line.into_iter().enumerate() {
+                    if n > 0 {
+                        writer.write_char(',')?;
+                    }
+                    let json = match value {
+                        Value::Str(value) => serde_json::to_string(&value),
+                        Value::Nanos(nanos) => serde_json::to_string(&nanos),
+                    };
+                    let key = serde_json::to_string(key).map_err(|_| fmt::Error)?;
+                    write!(writer, "{key}:{}", json.map_err(|_| fmt::Error)?)?;
+                }
+                writer.write_char('}')?;
+            }
+        }
+
+        writer.write_char('\n')
+    }
+}
+
+/// Returns the key to write a field with, prefixing keys that lines use for
+/// their own values.
+fn field_key(key: &str) -> &str {
+    match key {
+        "ts" => "field.ts",
+        "level" => "field.level",
+        "uuid" => "field.uuid",
+        "span" => "field.span",
+        "tag" => "field.tag",
+        "msg" => "field.msg",
+        "nanos_total" => "field.nanos_total",
+        "nanos_nested" => "field.nanos_nested",
+        key => key,
+    }
+}
+
+/// Writes a logfmt value, quoting and escaping it if needed.
+///
+/// Field values that were recorded from strings are already quoted by their
+/// `Debug` implementation, but they're escaped again like any other value,
+/// since a value recorded with `Display` can also start and end with quotes.
+/// This keeps a value from ending early and adding keys of its own.
+fn write_logfmt_value(value: &str, writer: &mut String) -> fmt::Result {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+
+    if needs_quotes {
+        write!(writer, "{value:?}")
+    } else {
+        writer.write_str(value)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::printer::Formatter;
+use crate::tree::Tree;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// Format logs as folded stacks, for generating flame graphs of span time.
+///
+/// Each span is written on a line with the names of the spans it's nested in,
+/// separated by semicolons, followed by its [base duration] in nanoseconds.
+/// Spans with the same stack in a single `Tree` are combined. Events aren't
+/// included, and semicolons in span names are replaced with colons.
+/// ```txt
+/// server;request 87000
+/// server;request;auth 1520000
+/// server;request;auth;db 310000
+/// ```
+/// The output can be passed directly to [inferno] or [`flamegraph.pl`]. To
+/// combine the stacks of all trees before writing them, use [`FoldedStacks`].
+///
+/// # Examples
+///
+/// ```
+/// use tracing_forest::printer::Folded;
+/// use tracing_forest::Printer;
+///
+/// let printer = Printer::new().formatter(Folded);
+/// ```
+///
+/// [base duration]: crate::tree::Span::base_duration
+/// [inferno]: https://github.com/jonhoo/inferno
+/// [`flamegraph.pl`]: https://github.com/brendangregg/FlameGraph
+/// [`FoldedStacks`]: crate::processor::folded::FoldedStacks
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Folded;
+
+impl Formatter for Folded {
+    type Error = fmt::Error;
+
+    fn fmt(&self, tree: &Tree) -> Result<String, fmt::Error> {
+        let mut stacks = BTreeMap::new();
+        fold(tree, &mut String::new(), &mut stacks);
+        write_stacks(&stacks)
+    }
+}
+
+/// Adds the base duration of every span in `tree` to `stacks`, where `stack`
+/// is the folded stack of the spans that `tree` is nested in.
+pub(crate) fn fold(tree: &Tree, stack: &mut String, stacks: &mut BTreeMap<String, u128>) {
+    let span = match tree {
+        Tree::Span(span) if span.should_render() => span,
+        _ => return,
+    };
+
+    let outer = stack.len();
+    if outer > 0 {
+        stack.push(';');
+    }
+    stack.extend(span.name().chars().map(|c| if c == ';' { ':' } else { c }));
+
+    let nanos = span.base_duration().as_nanos();
+    if nanos > 0 {
+        *stacks.entry(stack.clone()).or_default() += nanos;
+    }
+
+    for node in span.nodes() {
+        fold(node, stack, stacks);
+    }
+
+    stack.truncate(outer);
+}
+
+/// Writes each stack on its own line, followed by its weight.
+pub(crate) fn write_stacks(stacks: &BTreeMap<String, u128>) -> Result<String, fmt::Error> {
+    let mut string = String::new();
+    for (stack, nanos) in stacks {
+        writeln!(string, "{stack} {nanos}")?;
+    }
+    Ok(string)
+}
@@ -0,0 +1,81 @@
+use crate::printer::Formatter;
+use crate::tree::Tree;
+
+/// Format logs as JSON.
+///
+/// Each `Tree` is written as a single JSON document, with the nodes of spans
+/// nested inside them, in the same form that trees are [deserialized] from.
+/// By default, documents are compact and end with a newline, so the output is
+/// [newline-delimited JSON].
+///
+/// # Examples
+///
+/// Printing pretty JSON to stdout.
+/// ```
+/// use tracing_forest::printer::Json;
+/// use tracing_forest::{traits::*, util::*, Printer};
+/// use tracing_subscriber::Registry;
+///
+/// let printer = Printer::new().formatter(Json::new().pretty(true));
+///
+/// Registry::default()
+///     .with(ForestLayer::from(printer))
+///     .init();
+/// ```
+///
+/// [deserialized]: crate::tree::Tree
+/// [newline-delimited JSON]: https://github.com/ndjson/ndjson-spec
+#[derive(Clone, Copy, Debug)]
+pub struct Json {
+    pretty: bool,
+    newline: bool,
+}
+
+impl Json {
+    /// Returns a `Json` formatter that writes each `Tree` as compact JSON on
+    /// its own line.
+    pub const fn new() -> Self {
+        Json {
+            pretty: false,
+            newline: true,
+        }
+    }
+
+    /// Indent the JSON over multiple lines.
+    ///
+    /// Note that pretty JSON is no longer newline-delimited.
+    pub const fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// End each document with a newline.
+    pub const fn newline(mut self, newline: bool) -> Self {
+        self.newline = newline;
+        self
+    }
+}
+
+impl Default for Json {
+    fn default() -> Self {
+        Json::new()
+    }
+}
+
+impl Formatter for Json {
+    type Error = serde_json::Error;
+
+    fn fmt(&self, tree: &Tree) -> Result<String, serde_json::Error> {
+        let mut string = if self.pretty {
+            serde_json::to_string_pretty(tree)?
+        } else {
+            serde_json::to_string(tree)?
+        };
+
+        if self.newline {
+            string.push('\n');
+        }
+
+        Ok(string)
+    }
+}
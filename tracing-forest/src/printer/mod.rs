@@ -1,13 +1,55 @@
 //! Utilities for formatting and writing trace trees.
-use crate::processor::{self, Processor};
+use crate::processor::{self, batch, Processor};
 use crate::tree::Tree;
+use crate::{cfg_chrono, cfg_json};
+use std::any::Any;
 use std::error::Error;
-use std::io::{self, Write};
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tracing_subscriber::fmt::writer::{
+    BoxMakeWriter, OrElse, Tee, TestWriter, WithFilter, WithMaxLevel, WithMinLevel,
+};
 use tracing_subscriber::fmt::MakeWriter;
 
-mod pretty;
+mod color;
+pub use color::ColorMode;
+
+mod flat;
+pub use flat::Flat;
+
+pub(crate) mod folded;
+pub use folded::Folded;
+
+pub mod pretty;
 pub use pretty::Pretty;
 
+cfg_chrono! {
+    mod timestamp;
+    pub use timestamp::TimestampMode;
+}
+
+cfg_json! {
+    mod json;
+    pub use json::Json;
+}
+
+#[cfg(all(feature = "chrono", feature = "json"))]
+mod chrome;
+#[cfg(all(feature = "chrono", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "chrono", feature = "json"))))]
+pub use chrome::ChromeTrace;
+
+#[cfg(all(feature = "uuid", feature = "chrono", feature = "json"))]
+mod otlp;
+#[cfg(all(feature = "uuid", feature = "chrono", feature = "json"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "uuid", feature = "chrono", feature = "json")))
+)]
+pub use otlp::Otlp;
+
 /// Format a [`Tree`] into a `String`.
 ///
 /// # Examples
@@ -53,6 +95,31 @@ pub trait Formatter {
     ///
     /// If the `Tree` cannot be formatted to a string, an error is returned.
     fn fmt(&self, tree: &Tree) -> Result<String, Self::Error>;
+
+    /// Stringifies the `Tree` for a writer, or returns an error.
+    ///
+    /// `is_terminal` is whether to treat the writer as a terminal, which
+    /// formatters can use to decide whether to write color. The default implementation
+    /// ignores it and calls [`Formatter::fmt`].
+    ///
+    /// # Errors
+    ///
+    /// If the `Tree` cannot be formatted to a string, an error is returned.
+    fn fmt_for_writer(&self, tree: &Tree, is_terminal: bool) -> Result<String, Self::Error> {
+        let _ = is_terminal;
+        self.fmt(tree)
+    }
+
+    /// Returns what to write at the start of the output, before any trees.
+    ///
+    /// A [`Printer`] writes it before the first `Tree` it prints, and a
+    /// [`RollingFile`] at the start of each file. The default implementation
+    /// returns an empty string.
+    ///
+    /// [`RollingFile`]: crate::processor::file::RollingFile
+    fn header(&self) -> &'static str {
+        ""
+    }
 }
 
 impl<F, E> Formatter for F
@@ -73,6 +140,25 @@ where
 pub struct Printer<F, W> {
     formatter: F,
     make_writer: W,
+    is_terminal: Option<fn() -> bool>,
+    terminal: OnceLock<bool>,
+    started: Started,
+}
+
+/// Whether a [`Printer`] has written the header of its formatter yet.
+#[derive(Debug)]
+struct Started(AtomicBool);
+
+impl Started {
+    const fn new() -> Self {
+        Started(AtomicBool::new(false))
+    }
+}
+
+impl Clone for Started {
+    fn clone(&self) -> Self {
+        Started(AtomicBool::new(self.0.load(Ordering::Relaxed)))
+    }
 }
 
 /// A [`MakeWriter`] that writes to stdout.
@@ -89,6 +175,114 @@ pub struct MakeStdout;
 #[derive(Debug)]
 pub struct MakeStderr;
 
+/// A [`MakeWriter`] that can tell whether it writes to a terminal.
+///
+/// [`Printer::writer`] uses this to decide whether formatters like [`Pretty`]
+/// write color when configured with [`ColorMode::Auto`].
+///
+/// This is implemented for [`MakeStdout`], [`MakeStderr`], [`File`], and
+/// functions returning a writer, such as [`std::io::stderr`], which write to a
+/// terminal if the writer they return is a `Stdout`, `Stderr`, or `File` that
+/// is one. The `MakeWriter`s of `tracing-subscriber` never write to a terminal.
+///
+/// # Examples
+///
+/// Other `MakeWriter`s can use the default implementation, which never writes
+/// to a terminal, or check it themselves.
+/// ```
+/// use tracing_forest::printer::MakeTerminal;
+/// use tracing_subscriber::fmt::MakeWriter;
+/// use std::io::{self, IsTerminal};
+///
+/// struct MakeTty;
+///
+/// impl<'a> MakeWriter<'a> for MakeTty {
+///     type Writer = io::StderrLock<'static>;
+///
+///     fn make_writer(&'a self) -> Self::Writer {
+///         io::stderr().lock()
+///     }
+/// }
+///
+/// impl MakeTerminal for MakeTty {
+///     fn is_terminal(&self) -> bool {
+///         io::stderr().is_terminal()
+///     }
+/// }
+///
+/// let printer = tracing_forest::Printer::new().writer(MakeTty);
+/// ```
+///
+/// [`File`]: std::fs::File
+pub trait MakeTerminal {
+    /// Returns whether the writers made by `self` write to a terminal.
+    ///
+    /// The default implementation returns `false`.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+impl MakeTerminal for MakeStdout {
+    fn is_terminal(&self) -> bool {
+        io::stdout().is_terminal()
+    }
+}
+
+impl MakeTerminal for MakeStderr {
+    fn is_terminal(&self) -> bool {
+        io::stderr().is_terminal()
+    }
+}
+
+impl MakeTerminal for File {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(self)
+    }
+}
+
+impl<F, T> MakeTerminal for F
+where
+    F: Fn() -> T,
+    T: 'static,
+{
+    fn is_terminal(&self) -> bool {
+        let writer: &dyn Any = &self();
+
+        if let Some(stdout) = writer.downcast_ref::<io::Stdout>() {
+            stdout.is_terminal()
+        } else if let Some(stderr) = writer.downcast_ref::<io::Stderr>() {
+            stderr.is_terminal()
+        } else if let Some(stdout) = writer.downcast_ref::<io::StdoutLock<'static>>() {
+            stdout.is_terminal()
+        } else if let Some(stderr) = writer.downcast_ref::<io::StderrLock<'static>>() {
+            stderr.is_terminal()
+        } else if let Some(file) = writer.downcast_ref::<File>() {
+            IsTerminal::is_terminal(file)
+        } else {
+            false
+        }
+    }
+}
+
+impl<W> MakeTerminal for Arc<W> {}
+
+impl<W> MakeTerminal for Mutex<W> {}
+
+impl MakeTerminal for TestWriter {}
+
+impl MakeTerminal for BoxMakeWriter {}
+
+impl<M> MakeTerminal for WithMaxLevel<M> {}
+
+impl<M> MakeTerminal for WithMinLevel<M> {}
+
+impl<M, F> MakeTerminal for WithFilter<M, F> {}
+
+impl<A, B> MakeTerminal for Tee<A, B> {}
+
+impl<A, B> MakeTerminal for OrElse<A, B> {}
+
 impl MakeWriter<'_> for MakeStdout {
     type Writer = io::Stdout;
 
@@ -114,8 +308,11 @@ impl PrettyPrinter {
     /// Use [`Printer::formatter`] and [`Printer::writer`] for custom configuration.
     pub const fn new() -> Self {
         Printer {
-            formatter: Pretty,
+            formatter: Pretty::new(),
             make_writer: MakeStdout,
+            is_terminal: None,
+            terminal: OnceLock::new(),
+            started: Started::new(),
         }
     }
 }
@@ -123,7 +320,7 @@ impl PrettyPrinter {
 impl<F, W> Printer<F, W>
 where
     F: 'static + Formatter,
-    W: 'static + for<'a> MakeWriter<'a>,
+    W: 'static + for<'a> MakeWriter<'a> + MakeTerminal,
 {
     /// Set the formatter.
     ///
@@ -135,17 +332,60 @@ where
         Printer {
             formatter,
             make_writer: self.make_writer,
+            is_terminal: self.is_terminal,
+            terminal: self.terminal,
+            started: Started::new(),
         }
     }
 
     /// Set the writer.
+    ///
+    /// The writer is asked whether it writes to a terminal the first time a
+    /// `Tree` is written, which formatters like [`Pretty`] use to decide
+    /// whether to write color when configured with [`ColorMode::Auto`]. See
+    /// [`MakeTerminal`] for more details.
     pub fn writer<W2>(self, make_writer: W2) -> Printer<F, W2>
     where
-        W2: 'static + for<'a> MakeWriter<'a>,
+        W2: 'static + for<'a> MakeWriter<'a> + MakeTerminal,
     {
         Printer {
             formatter: self.formatter,
             make_writer,
+            is_terminal: None,
+            terminal: OnceLock::new(),
+            started: Started::new(),
+        }
+    }
+
+    /// Set how to check whether the writer is a terminal, instead of asking
+    /// the writer.
+    ///
+    /// This is used by formatters like [`Pretty`] to decide whether to write
+    /// color when configured with [`ColorMode::Auto`]. Unlike the answer of
+    /// the writer, it isn't overridden by environment variables.
+    pub fn is_terminal(mut self, is_terminal: fn() -> bool) -> Self {
+        self.is_terminal = Some(is_terminal);
+        self.terminal = OnceLock::new();
+        self
+    }
+
+    /// Returns whether to treat the writer as a terminal, which is only
+    /// checked once.
+    fn terminal(&self) -> bool {
+        *self.terminal.get_or_init(|| match self.is_terminal {
+            Some(is_terminal) => is_terminal(),
+            None => color::treat_as_terminal(&self.make_writer),
+        })
+    }
+
+    /// Prepends the header of the formatter to `string` if it's the first
+    /// thing this `Printer` writes.
+    fn with_header(&self, string: String) -> String {
+        let header = self.formatter.header();
+        if header.is_empty() || self.started.0.swap(true, Ordering::Relaxed) {
+            string
+        } else {
+            header.to_string() + &string
         }
     }
 }
@@ -159,11 +399,11 @@ impl Default for PrettyPrinter {
 impl<F, W> Processor for Printer<F, W>
 where
     F: 'static + Formatter,
-    W: 'static + for<'a> MakeWriter<'a>,
+    W: 'static + for<'a> MakeWriter<'a> + MakeTerminal,
 {
     fn process(&self, tree: Tree) -> processor::Result {
-        let string = match self.formatter.fmt(&tree) {
-            Ok(s) => s,
+        let string = match self.formatter.fmt_for_writer(&tree, self.terminal()) {
+            Ok(s) => self.with_header(s),
             Err(e) => return Err(processor::error(tree, e.into())),
         };
 
@@ -172,6 +412,34 @@ where
             Err(e) => Err(processor::error(tree, e.into())),
         }
     }
+
+    fn flush(&self) -> processor::FlushResult {
+        Ok(self.make_writer.make_writer().flush()?)
+    }
+}
+
+impl<F, W> batch::BatchProcessor for Printer<F, W>
+where
+    F: 'static + Formatter,
+    W: 'static + for<'a> MakeWriter<'a> + MakeTerminal,
+{
+    fn process_batch(&self, trees: Vec<Tree>) -> batch::Result {
+        let mut string = String::new();
+        let is_terminal = self.terminal();
+
+        for tree in &trees {
+            match self.formatter.fmt_for_writer(tree, is_terminal) {
+                Ok(s) => string.push_str(&s),
+                Err(e) => return Err(batch::error(trees, e.into())),
+            }
+        }
+
+        let string = self.with_header(string);
+        match self.make_writer.make_writer().write_all(string.as_bytes()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(batch::error(trees, e.into())),
+        }
+    }
 }
 
 /// A [`Processor`] that captures logs during tests and allows them to be presented
@@ -186,7 +454,9 @@ impl TestCapturePrinter<Pretty> {
     /// is intented for use in tests only as it works with the default rust stdout capture mechanism
     #[allow(clippy::new_without_default)]
     pub const fn new() -> Self {
-        TestCapturePrinter { formatter: Pretty }
+        TestCapturePrinter {
+            formatter: Pretty::new(),
+        }
     }
 }
 
@@ -197,7 +467,7 @@ where
     fn process(&self, tree: Tree) -> processor::Result {
         let string = self
             .formatter
-            .fmt(&tree)
+            .fmt_for_writer(&tree, color::treat_as_terminal(&MakeStdout))
             .map_err(|e| processor::error(tree, e.into()))?;
 
         print!("{string}");
@@ -0,0 +1,300 @@
+use crate::printer::Formatter;
+use crate::tree::{Event, Field, Span, Tree};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::time::Duration;
+use tracing::Level;
+
+/// Format logs as [OTLP/JSON], so they can be loaded into OpenTelemetry tools
+/// without running a collector.
+///
+/// Each `Tree` is written on its own line as an `ExportTraceServiceRequest`
+/// containing a single `ResourceSpans`, which is the format written by the
+/// OpenTelemetry Collector's file exporter. Trees that are a single event
+/// outside of any span are written as an `ExportLogsServiceRequest` containing
+/// a single log record instead, since OTLP span events belong to spans.
+///
+/// * Spans become spans, whose trace id is the [`Uuid`] of the root span. A
+///   span whose uuid differs from its parent's, such as one given a `uuid`
+///   field, uses the low 64 bits of its own uuid as its span id. Other spans
+///   inherit their uuid, so their span ids are the low 64 bits of the root's
+///   uuid plus the position of the span in the tree.
+/// * Events become span events of the span they occurred in, named after
+///   their message, or log records if they aren't in a span.
+/// * Fields become attributes. Values are recorded with their `Debug`
+///   implementation, so booleans and numbers are converted back into typed
+///   values, and string values are unquoted.
+/// * Levels and tags are recorded in the `level` and `tag` attributes.
+///
+/// Spans end once they have been entered for their [total duration] after
+/// opening, or at the end of their last [interval] if the `timeline` feature
+/// is enabled.
+///
+/// # Examples
+///
+/// Writing trees to a file that can be imported by OpenTelemetry tools.
+/// ```no_run
+/// use tracing_forest::printer::Otlp;
+/// use tracing_forest::processor::file::RollingFile;
+/// use tracing_forest::{traits::*, util::*};
+/// use tracing_subscriber::Registry;
+///
+/// let processor = RollingFile::new(".", "traces.jsonl")
+///     .formatter(Otlp::new().service_name("my-app"));
+///
+/// Registry::default()
+///     .with(ForestLayer::from(processor))
+///     .init();
+/// ```
+///
+/// [OTLP/JSON]: https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding
+/// [`Uuid`]: uuid::Uuid
+/// [total duration]: crate::tree::Span::total_duration
+/// [interval]: crate::tree::Span::intervals
+#[derive(Clone, Debug)]
+pub struct Otlp {
+    service_name: Cow<'static, str>,
+}
+
+impl Otlp {
+    /// Returns an `Otlp` formatter for the service `unknown_service`.
+    pub const fn new() -> Self {
+        Otlp {
+            service_name: Cow::Borrowed("unknown_service"),
+        }
+    }
+
+    /// Set the `service.name` resource attribute.
+    pub fn service_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.service_name = name.into();
+        self
+    }
+}
+
+impl Default for Otlp {
+    fn default() -> Self {
+        Otlp::new()
+    }
+}
+
+impl Formatter for Otlp {
+    type Error = serde_json::Error;
+
+    fn fmt(&self, tree: &Tree) -> Result<String, serde_json::Error> {
+        let root = match tree {
+            Tree::Span(span) if span.should_render() => span,
+            Tree::Span(_) => return Ok(String::new()),
+            Tree::Event(event) => return self.fmt_log(event),
+        };
+
+        let trace = Trace {
+            id: root.uuid().as_u128(),
+            root: root.timestamp(),
+        };
+        let mut spans = Vec::new();
+        trace.push_span(root, None, &mut spans);
+
+        let request = json!({
+            "resourceSpans": [{
+                "resource": self.resource(),
+                "scopeSpans": [{
+                    "scope": scope(),
+                    "spans": spans,
+                }],
+            }],
+        });
+
+        line(&request)
+    }
+}
+
+impl Otlp {
+    /// Formats an event outside of any span as a log record.
+    fn fmt_log(&self, event: &Event) -> Result<String, serde_json::Error> {
+        let mut attributes = attributes(event.fields());
+        if let Some(tag) = event.tag() {
+            attributes.push(attribute("tag", string_value(tag.to_string())));
+        }
+
+        let request = json!({
+            "resourceLogs": [{
+                "resource": self.resource(),
+                "scopeLogs": [{
+                    "scope": scope(),
+                    "logRecords": [{
+                        "timeUnixNano": unix_nanos(event.timestamp()),
+                        "severityNumber": severity_number(event.level()),
+                        "severityText": event.level().as_str(),
+                        "body": string_value(event.message().unwrap_or_default()),
+                        "attributes": attributes,
+                    }],
+                }],
+            }],
+        });
+
+        line(&request)
+    }
+
+    fn resource(&self) -> Value {
+        json!({
+            "attributes": [attribute("service.name", string_value(&*self.service_name))],
+        })
+    }
+}
+
+fn scope() -> Value {
+    json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// Writes `request` as a line of JSON.
+fn line(request: &Value) -> Result<String, serde_json::Error> {
+    let mut string = serde_json::to_string(request)?;
+    string.push('\n');
+    Ok(string)
+}
+
+/// Returns the OTLP `SeverityNumber` of the lowest severity for `level`.
+fn severity_number(level: Level) -> u8 {
+    match level {
+        Level::TRACE => 1,
+        Level::DEBUG => 5,
+        Level::INFO => 9,
+        Level::WARN => 13,
+        Level::ERROR => 17,
+    }
+}
+
+/// The spans of a single `Tree`.
+struct Trace {
+    id: u128,
+    /// When the root span opened.
+    #[cfg_attr(not(feature = "timeline"), allow(dead_code))]
+    root: DateTime<Utc>,
+}
+
+impl Trace {
+    /// Returns the span id of `span`, which is at `index` in preorder.
+    fn span_id(&self, span: &Span, parent: Option<&Span>, index: usize) -> String {
+        let uuid = span.uuid().as_u128();
+        let id = match parent {
+            Some(parent) if parent.uuid().as_u128() != uuid => uuid as u64,
+            _ => (self.id as u64).wrapping_add(index as u64 + 1),
+        };
+        format!("{id:016x}")
+    }
+
+    fn push_span(&self, span: &Span, parent: Option<(&Span, &str)>, spans: &mut Vec<Value>) {
+        let index = spans.len();
+        let span_id = self.span_id(span, parent.map(|(parent, _)| parent), index);
+        spans.push(Value::Null);
+
+        let mut events = Vec::new();
+        for node in span.nodes() {
+            match node {
+                Tree::Event(event) => events.push(span_event(event)),
+                Tree::Span(child) if child.should_render() => {
+                    self.push_span(child, Some((span, &span_id)), spans);
+                }
+                Tree::Span(_) => {}
+            }
+        }
+
+        let mut attributes = attributes(span.fields());
+        attributes.push(attribute("level", string_value(span.level().as_str())));
+
+        let mut otlp_span = json!({
+            "traceId": format!("{:032x}", self.id),
+            "spanId": span_id,
+            "name": span.name(),
+            "kind": 1,
+            "startTimeUnixNano": unix_nanos(span.timestamp()),
+            "endTimeUnixNano": unix_nanos(self.end(span)),
+            "attributes": attributes,
+            "events": events,
+        });
+        if let Some((_, parent_id)) = parent {
+            otlp_span["parentSpanId"] = parent_id.into();
+        }
+
+        spans[index] = otlp_span;
+    }
+
+    #[cfg(feature = "timeline")]
+    fn end(&self, span: &Span) -> DateTime<Utc> {
+        match span.intervals().last() {
+            Some(interval) => after(self.root, interval.end()),
+            None => after(span.timestamp(), span.total_duration()),
+        }
+    }
+
+    #[cfg(not(feature = "timeline"))]
+    fn end(&self, span: &Span) -> DateTime<Utc> {
+        after(span.timestamp(), span.total_duration())
+    }
+}
+
+fn span_event(event: &Event) -> Value {
+    let mut attributes = attributes(event.fields());
+    attributes.push(attribute("level", string_value(event.level().as_str())));
+    if let Some(tag) = event.tag() {
+        attributes.push(attribute("tag", string_value(tag.to_string())));
+    }
+
+    json!({
+        "timeUnixNano": unix_nanos(event.timestamp()),
+        "name": event.message().unwrap_or("event"),
+        "attributes": attributes,
+    })
+}
+
+/// Returns `duration` after `start`, or `start` if that is out of range.
+fn after(start: DateTime<Utc>, duration: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| start.checked_add_signed(duration))
+        .unwrap_or(start)
+}
+
+/// Returns nanoseconds since the Unix epoch as a string, since OTLP/JSON
+/// encodes 64 bit integers as strings.
+fn unix_nanos(timestamp: DateTime<Utc>) -> String {
+    timestamp
+        .timestamp_nanos_opt()
+        .map_or(0, |nanos| nanos.max(0))
+        .to_string()
+}
+
+fn attributes(fields: &[Field]) -> Vec<Value> {
+    fields
+        .iter()
+        .map(|field| attribute(field.key(), any_value(field.value())))
+        .collect()
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+fn string_value(value: impl Into<String>) -> Value {
+    json!({ "stringValue": value.into() })
+}
+
+/// Converts a `Debug` formatted value into an OTLP `AnyValue`.
+fn any_value(value: &str) -> Value {
+    if let Ok(value) = value.parse::<bool>() {
+        json!({ "boolValue": value })
+    } else if let Ok(value) = value.parse::<i64>() {
+        json!({ "intValue": value.to_string() })
+    } else if let Some(value) = value.parse::<f64>().ok().filter(|value| value.is_finite()) {
+        json!({ "doubleValue": value })
+    } else {
+        // `Debug` escapes strings much like JSON does, so most string values
+        // can be unquoted by parsing them as JSON.
+        let string = serde_json::from_str::<String>(value).unwrap_or_else(|_| value.to_string());
+        string_value(string)
+    }
+}
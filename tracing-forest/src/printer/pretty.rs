@@ -1,17 +1,26 @@
-use crate::printer::Formatter;
+//! Configurable pretty formatting of trees.
+//!
+//! See [`Pretty`] for more details.
+#[cfg(feature = "chrono")]
+use crate::printer::TimestampMode;
+use crate::printer::{ColorMode, Formatter};
 use crate::tree::{Event, Shared, Span, Tree};
 use crate::Tag;
+use std::borrow::Cow;
 use std::fmt::{self, Write};
+use std::time::Duration;
+use tracing::Level;
+use unicode_width::UnicodeWidthChar;
 
 #[cfg(feature = "smallvec")]
 type IndentVec = smallvec::SmallVec<[Indent; 32]>;
 #[cfg(not(feature = "smallvec"))]
 type IndentVec = Vec<Indent>;
 
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
 #[cfg(feature = "ansi")]
 use nu_ansi_term::Color;
-#[cfg(feature = "ansi")]
-use tracing::Level;
 
 /// Format logs for pretty printing.
 ///
@@ -58,33 +67,327 @@ use tracing::Level;
 /// WARN     │     ┕━ 🚧 [filter.warn]: Some filter warning
 /// TRACE    ┕━ 📍 [trace]: Finished!
 /// ```
-#[derive(Debug)]
-pub struct Pretty;
+///
+/// # Configuration
+///
+/// The default `Pretty` shows everything that the enabled features record. Use
+/// [`Pretty::builder`] to choose what is shown at runtime, for example to
+/// print compact output for local development.
+/// ```
+/// use tracing_forest::printer::Pretty;
+/// use tracing_forest::Printer;
+///
+/// let compact = Pretty::builder()
+///     .uuid(false)
+///     .timestamp(false)
+///     .percentages(false)
+///     .align_columns(false)
+///     .build();
+///
+/// let printer = Printer::new().formatter(compact);
+/// ```
+///
+/// Deep trees can be shortened by collapsing parts of them into a summary
+/// line with [`Builder::max_depth`], [`Builder::collapse_below`], and
+/// [`Builder::collapse_below_level`]. This only affects what is printed.
+///
+/// Output that is viewed somewhere without Unicode support, such as some CI
+/// log viewers, can be drawn with plain ASCII instead.
+/// ```
+/// use tracing_forest::printer::pretty::{Glyphs, IconMode};
+/// use tracing_forest::printer::Pretty;
+///
+/// let ascii = Pretty::builder()
+///     .glyphs(Glyphs::ASCII)
+///     .icon_mode(IconMode::Text)
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pretty {
+    uuid: bool,
+    timestamp: bool,
+    #[cfg(feature = "chrono")]
+    timestamp_mode: TimestampMode,
+    /// The width of `timestamp_mode`, which is computed once since it isn't
+    /// trivial for custom patterns.
+    #[cfg(feature = "chrono")]
+    timestamp_width: usize,
+    level: bool,
+    icon: bool,
+    icon_mode: IconMode,
+    glyphs: Glyphs,
+    durations: bool,
+    percentages: bool,
+    fields: bool,
+    field_separator: Cow<'static, str>,
+    align_columns: bool,
+    color: ColorMode,
+    max_depth: Option<usize>,
+    collapse_duration: Option<Duration>,
+    collapse_level: Option<Level>,
+    wrap: Option<usize>,
+}
+
+/// Incrementally configure a [`Pretty`] formatter.
+///
+/// See [`Pretty::builder`] for more details.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    pretty: Pretty,
+}
+
+impl Pretty {
+    /// Returns a `Pretty` formatter that shows everything.
+    pub const fn new() -> Self {
+        Pretty {
+            uuid: true,
+            timestamp: true,
+            #[cfg(feature = "chrono")]
+            timestamp_mode: TimestampMode::Rfc3339,
+            #[cfg(feature = "chrono")]
+            timestamp_width: TimestampMode::RFC3339_WIDTH,
+            level: true,
+            icon: true,
+            icon_mode: IconMode::Emoji,
+            glyphs: Glyphs::HEAVY,
+            durations: true,
+            percentages: true,
+            fields: true,
+            field_separator: Cow::Borrowed(" | "),
+            align_columns: true,
+            color: ColorMode::Auto,
+            max_depth: None,
+            collapse_duration: None,
+            collapse_level: None,
+            wrap: None,
+        }
+    }
+
+    /// Returns a [`Builder`] for configuring a `Pretty` formatter, starting
+    /// from the default configuration.
+    pub const fn builder() -> Builder {
+        Builder {
+            pretty: Pretty::new(),
+        }
+    }
+}
+
+impl Default for Pretty {
+    fn default() -> Self {
+        Pretty::new()
+    }
+}
+
+impl Builder {
+    /// Show the [`Uuid`] of each span and event.
+    ///
+    /// This has no effect unless the `uuid` feature is enabled.
+    ///
+    /// [`Uuid`]: uuid::Uuid
+    pub const fn uuid(mut self, show: bool) -> Self {
+        self.pretty.uuid = show;
+        self
+    }
+
+    /// Show the timestamp of each span and event.
+    ///
+    /// This has no effect unless the `chrono` feature is enabled.
+    pub const fn timestamp(mut self, show: bool) -> Self {
+        self.pretty.timestamp = show;
+        self
+    }
+
+    /// Set how timestamps are displayed.
+    ///
+    /// The default is [`TimestampMode::Rfc3339`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mode` has an invalid [`strftime`] pattern.
+    ///
+    /// [`strftime`]: chrono::format::strftime
+    #[cfg(feature = "chrono")]
+    pub fn timestamp_mode(mut self, mode: TimestampMode) -> Self {
+        mode.validate();
+        self.pretty.timestamp_width = mode.width();
+        self.pretty.timestamp_mode = mode;
+        self
+    }
+
+    /// Show the level of each span and event.
+    pub const fn level(mut self, show: bool) -> Self {
+        self.pretty.level = show;
+        self
+    }
+
+    /// Show the icon of each event's [`Tag`].
+    pub const fn icon(mut self, show: bool) -> Self {
+        self.pretty.icon = show;
+        self
+    }
+
+    /// Set how icons are displayed.
+    ///
+    /// The default is [`IconMode::Emoji`].
+    pub const fn icon_mode(mut self, mode: IconMode) -> Self {
+        self.pretty.icon_mode = mode;
+        self
+    }
+
+    /// Set the glyphs that the tree is drawn with.
+    ///
+    /// The default is [`Glyphs::HEAVY`].
+    pub const fn glyphs(mut self, glyphs: Glyphs) -> Self {
+        self.pretty.glyphs = glyphs;
+        self
+    }
+
+    /// Show how long each span was entered for.
+    pub const fn durations(mut self, show: bool) -> Self {
+        self.pretty.durations = show;
+        self
+    }
+
+    /// Show how much of the root span's duration each span accounts for.
+    pub const fn percentages(mut self, show: bool) -> Self {
+        self.pretty.percentages = show;
+        self
+    }
+
+    /// Show the fields of each span and event.
+    pub const fn fields(mut self, show: bool) -> Self {
+        self.pretty.fields = show;
+        self
+    }
+
+    /// Set the separator written between fields.
+    ///
+    /// The default is `" | "`.
+    pub fn field_separator(mut self, separator: impl Into<Cow<'static, str>>) -> Self {
+        self.pretty.field_separator = separator.into();
+        self
+    }
+
+    /// Pad the uuid, timestamp, and level columns to a fixed width so that they
+    /// line up.
+    ///
+    /// If disabled, columns are separated by a single space instead.
+    pub const fn align_columns(mut self, align: bool) -> Self {
+        self.pretty.align_columns = align;
+        self
+    }
+
+    /// Set when levels and field keys are colored.
+    ///
+    /// The default is [`ColorMode::Auto`], which colors output written to a
+    /// terminal by a [`Printer`]. This has no effect unless the `ansi` feature
+    /// is enabled.
+    ///
+    /// [`Printer`]: crate::Printer
+    pub const fn color(mut self, mode: ColorMode) -> Self {
+        self.pretty.color = mode;
+        self
+    }
+
+    /// Collapse the children of spans nested `depth` levels below the root
+    /// into a summary line.
+    ///
+    /// The root of the tree is at depth 0, so a depth of 0 only shows the root
+    /// span and a summary of everything inside it.
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.pretty.max_depth = Some(depth);
+        self
+    }
+
+    /// Collapse the children of spans that were entered for less than
+    /// `duration` into a summary line.
+    pub const fn collapse_below(mut self, duration: Duration) -> Self {
+        self.pretty.collapse_duration = Some(duration);
+        self
+    }
+
+    /// Collapse the children of spans into a summary line when none of them,
+    /// or their descendants, are at least as severe as `level`.
+    ///
+    /// For example, `collapse_below_level(Level::INFO)` hides subtrees that
+    /// only contain `DEBUG` and `TRACE` spans and events.
+    pub const fn collapse_below_level(mut self, level: Level) -> Self {
+        self.pretty.collapse_level = Some(level);
+        self
+    }
+
+    /// Wrap events at spaces so that lines fit in `width` columns where
+    /// possible.
+    ///
+    /// Words that are too long to fit are not split. Lines that are nested so
+    /// deeply that there is little room left are wrapped to a minimum of 20
+    /// columns instead.
+    ///
+    /// The width isn't detected, so to wrap to the width of a terminal, query
+    /// its size when building the formatter and pass its width here.
+    pub const fn wrap(mut self, width: usize) -> Self {
+        self.pretty.wrap = Some(width);
+        self
+    }
+
+    /// Complete the [`Pretty`] formatter.
+    pub fn build(self) -> Pretty {
+        self.pretty
+    }
+}
 
 impl Formatter for Pretty {
     type Error = fmt::Error;
 
     fn fmt(&self, tree: &Tree) -> Result<String, fmt::Error> {
+        self.fmt_for_writer(tree, false)
+    }
+
+    fn fmt_for_writer(&self, tree: &Tree, is_terminal: bool) -> Result<String, fmt::Error> {
         let mut writer = String::with_capacity(256);
+        let mut state = State {
+            color: cfg!(feature = "ansi") && self.color.enabled(is_terminal),
+            ..State::default()
+        };
 
-        Pretty::format_tree(tree, None, &mut IndentVec::new(), &mut writer)?;
+        self.format_tree(tree, None, &mut state, &mut writer)?;
 
         Ok(writer)
     }
 }
 
+/// The state of formatting a single tree.
+#[derive(Default)]
+struct State {
+    indent: IndentVec,
+
+    /// Whether to write color.
+    color: bool,
+
+    /// The timestamp of the root of the tree.
+    #[cfg(feature = "chrono")]
+    root_timestamp: Option<DateTime<Utc>>,
+
+    /// The timestamp of the previous line.
+    #[cfg(feature = "chrono")]
+    previous_timestamp: Option<DateTime<Utc>>,
+}
+
 impl Pretty {
     fn format_tree(
+        &self,
         tree: &Tree,
         duration_root: Option<f64>,
-        indent: &mut IndentVec,
+        state: &mut State,
         writer: &mut String,
     ) -> fmt::Result {
         match tree {
             Tree::Event(event) => {
-                Pretty::format_shared(&event.shared, writer)?;
-                Pretty::format_indent(indent, writer)?;
-                Pretty::format_event(event, writer)
+                let line_start = writer.len();
+                self.format_shared(&event.shared, state, writer)?;
+                let columns = display_width(&writer[line_start..]);
+                self.format_indent(&state.indent, writer)?;
+                self.format_event(event, columns, state, writer)
             }
             Tree::Span(span) => {
                 // This needs to be called before all other actions are taken. If we don't
@@ -94,54 +397,151 @@ impl Pretty {
                     return Ok(());
                 }
 
-                Pretty::format_shared(&span.shared, writer)?;
-                Pretty::format_indent(indent, writer)?;
-                Pretty::format_span(span, duration_root, indent, writer)
+                let line_start = writer.len();
+                self.format_shared(&span.shared, state, writer)?;
+                let columns = display_width(&writer[line_start..]);
+                self.format_indent(&state.indent, writer)?;
+                self.format_span(span, duration_root, columns, state, writer)
             }
         }
     }
 
-    fn format_shared(shared: &Shared, writer: &mut String) -> fmt::Result {
+    #[cfg_attr(
+        not(any(feature = "chrono", feature = "ansi")),
+        allow(unused_variables)
+    )]
+    fn format_shared(
+        &self,
+        shared: &Shared,
+        state: &mut State,
+        writer: &mut String,
+    ) -> fmt::Result {
         #[cfg(feature = "uuid")]
-        write!(writer, "{} ", shared.uuid)?;
+        if self.uuid {
+            write!(writer, "{} ", shared.uuid)?;
+        }
 
         #[cfg(feature = "chrono")]
-        write!(writer, "{:<36} ", shared.timestamp.to_rfc3339())?;
+        {
+            let root = *state.root_timestamp.get_or_insert(shared.timestamp);
+            let previous = state.previous_timestamp.replace(shared.timestamp);
+
+            if self.timestamp {
+                let width = if self.align_columns {
+                    self.timestamp_width
+                } else {
+                    0
+                };
+                let timestamp = self
+                    .timestamp_mode
+                    .format(shared.timestamp, Some(root), previous);
+                write!(writer, "{timestamp:<width$} ")?;
+            }
+        }
 
-        #[cfg(feature = "ansi")]
-        return write!(writer, "{:<8} ", ColorLevel(shared.level));
+        if self.level {
+            let width = if self.align_columns { 8 } else { 0 };
+
+            #[cfg(feature = "ansi")]
+            if state.color {
+                write!(writer, "{:<width$} ", ColorLevel(shared.level))?;
+                return Ok(());
+            }
 
-        #[cfg(not(feature = "ansi"))]
-        return write!(writer, "{:<8} ", shared.level);
+            write!(writer, "{:<width$} ", shared.level)?;
+        }
+
+        Ok(())
     }
 
-    fn format_indent(indent: &[Indent], writer: &mut String) -> fmt::Result {
+    fn format_indent(&self, indent: &[Indent], writer: &mut String) -> fmt::Result {
         for indent in indent {
-            writer.write_str(indent.repr())?;
+            writer.write_str(indent.repr(&self.glyphs))?;
         }
         Ok(())
     }
 
-    fn format_event(event: &Event, writer: &mut String) -> fmt::Result {
+    /// Writes the body of an event, whose line starts with `columns` columns
+    /// of uuid, timestamp, and level.
+    fn format_event(
+        &self,
+        event: &Event,
+        columns: usize,
+        state: &State,
+        writer: &mut String,
+    ) -> fmt::Result {
         let tag = event.tag().unwrap_or_else(|| Tag::from(event.level()));
+        let mut body = String::with_capacity(128);
+
+        if self.icon {
+            match self.icon_mode {
+                IconMode::Emoji => write!(body, "{} ", tag.icon())?,
+                IconMode::Text => write!(body, "{} ", level_text(event.level()))?,
+            }
+        }
 
-        write!(writer, "{} [{}]: ", tag.icon(), tag)?;
+        write!(body, "[{tag}]: ")?;
 
         if let Some(message) = event.message() {
-            writer.write_str(message)?;
+            body.write_str(message)?;
+        }
+
+        if self.fields {
+            for field in event.fields() {
+                write!(
+                    body,
+                    "{}{}: {}",
+                    self.field_separator,
+                    FieldKey(field.key(), state.color),
+                    field.value()
+                )?;
+            }
         }
 
-        for field in event.fields() {
-            write!(writer, " | {}: {}", FieldKey(field.key()), field.value())?;
+        let continuation = self.continuation(columns, state);
+        let width = self
+            .wrap
+            .map(|width| width.saturating_sub(display_width(&continuation)))
+            .map(|width| width.max(MIN_WRAP_WIDTH));
+
+        for (n, line) in body.lines().enumerate() {
+            if n > 0 {
+                writer.push_str(&continuation);
+            }
+            match width {
+                Some(width) => wrap_line(line, width, &continuation, writer),
+                None => writer.push_str(line),
+            }
+            writer.push('\n');
         }
 
-        writeln!(writer)
+        Ok(())
+    }
+
+    /// Returns the start of the continuation lines of a node whose line starts
+    /// with `columns` columns of uuid, timestamp, and level.
+    ///
+    /// Continuation lines line up with the first line, and keep drawing the
+    /// branches of the tree to their left.
+    fn continuation(&self, columns: usize, state: &State) -> String {
+        let mut continuation = " ".repeat(columns);
+        for indent in &state.indent {
+            continuation.push_str(match indent {
+                Indent::Fork | Indent::Line => self.glyphs.line,
+                Indent::Turn | Indent::Null => self.glyphs.blank,
+            });
+        }
+        continuation
     }
 
+    /// Writes a span and its children, where the line of the span starts with
+    /// `columns` columns of uuid, timestamp, and level.
     fn format_span(
+        &self,
         span: &Span,
         duration_root: Option<f64>,
-        indent: &mut IndentVec,
+        columns: usize,
+        state: &mut State,
         writer: &mut String,
     ) -> fmt::Result {
         let total_duration = span.total_duration().as_nanos() as f64;
@@ -149,31 +549,54 @@ impl Pretty {
         let root_duration = duration_root.unwrap_or(total_duration);
         let percent_total_of_root_duration = 100.0 * total_duration / root_duration;
 
-        write!(
-            writer,
-            "{} [ {} | ",
-            span.name(),
-            DurationDisplay(total_duration)
-        )?;
+        let mut body = String::with_capacity(128);
+        body.write_str(span.name())?;
 
-        if inner_duration > 0.0 {
-            let base_duration = span.base_duration().as_nanos() as f64;
-            let percent_base_of_root_duration = 100.0 * base_duration / root_duration;
-            write!(writer, "{percent_base_of_root_duration:.2}% / ")?;
+        if self.durations || self.percentages {
+            body.write_str(" [ ")?;
+
+            if self.durations {
+                write!(body, "{}", DurationDisplay(total_duration))?;
+            }
+
+            if self.durations && self.percentages {
+                body.write_str(" | ")?;
+            }
+
+            if self.percentages {
+                if inner_duration > 0.0 {
+                    let base_duration = span.base_duration().as_nanos() as f64;
+                    let percent_base_of_root_duration = 100.0 * base_duration / root_duration;
+                    write!(body, "{percent_base_of_root_duration:.2}% / ")?;
+                }
+
+                write!(body, "{percent_total_of_root_duration:.2}%")?;
+            }
+
+            body.write_str(" ]")?;
         }
 
-        write!(writer, "{percent_total_of_root_duration:.2}% ]")?;
+        if self.fields {
+            for (n, field) in span.shared.fields.iter().enumerate() {
+                write!(
+                    body,
+                    "{}{}: {}",
+                    if n == 0 { " " } else { &self.field_separator },
+                    FieldKey(field.key(), state.color),
+                    field.value()
+                )?;
+            }
+        }
 
-        for (n, field) in span.shared.fields.iter().enumerate() {
-            write!(
-                writer,
-                "{} {}: {}",
-                if n == 0 { "" } else { " |" },
-                FieldKey(field.key()),
-                field.value()
-            )?;
+        // Field values can span lines, which line up with the first line.
+        let continuation = self.continuation(columns, state);
+        for (n, line) in body.lines().enumerate() {
+            if n > 0 {
+                writer.push_str(&continuation);
+            }
+            writer.push_str(line);
+            writer.push('\n');
         }
-        writeln!(writer)?;
 
         // We need to limit our nodes to those that *will* render, else we
         // end up printing ghost indents.
@@ -183,32 +606,261 @@ impl Pretty {
             .filter(|node| node.should_render())
             .collect();
 
-        if let Some((last, remaining)) = nodes.split_last() {
-            match indent.last_mut() {
-                Some(edge @ Indent::Turn) => *edge = Indent::Null,
-                Some(edge @ Indent::Fork) => *edge = Indent::Line,
-                _ => {}
-            }
+        if nodes.is_empty() {
+            return Ok(());
+        }
 
-            indent.push(Indent::Fork);
+        match state.indent.last_mut() {
+            Some(edge @ Indent::Turn) => *edge = Indent::Null,
+            Some(edge @ Indent::Fork) => *edge = Indent::Line,
+            _ => {}
+        }
+
+        if self.should_collapse(span, state.indent.len()) {
+            state.indent.push(Indent::Turn);
+            self.format_shared(&span.shared, state, writer)?;
+            self.format_indent(&state.indent, writer)?;
+            self.format_summary(&nodes, writer)?;
+            state.indent.pop();
+        } else if let Some((last, remaining)) = nodes.split_last() {
+            state.indent.push(Indent::Fork);
 
             for tree in remaining {
-                if let Some(edge) = indent.last_mut() {
+                if let Some(edge) = state.indent.last_mut() {
                     *edge = Indent::Fork;
                 }
-                Pretty::format_tree(tree, Some(root_duration), indent, writer)?;
+                self.format_tree(tree, Some(root_duration), state, writer)?;
             }
 
-            if let Some(edge) = indent.last_mut() {
+            if let Some(edge) = state.indent.last_mut() {
                 *edge = Indent::Turn;
             }
-            Pretty::format_tree(last, Some(root_duration), indent, writer)?;
+            self.format_tree(last, Some(root_duration), state, writer)?;
 
-            indent.pop();
+            state.indent.pop();
         }
 
         Ok(())
     }
+
+    /// Returns whether the children of `span`, which is nested `depth` levels
+    /// below the root, should be collapsed into a summary line.
+    fn should_collapse(&self, span: &Span, depth: usize) -> bool {
+        self.max_depth.is_some_and(|max| depth >= max)
+            || self
+                .collapse_duration
+                .is_some_and(|min| span.total_duration() < min)
+            || self
+                .collapse_level
+                .is_some_and(|level| !span.nodes().iter().any(|node| is_at_least(node, level)))
+    }
+
+    /// Writes a line saying how many spans and events are in `nodes`, and the
+    /// combined duration of the spans.
+    fn format_summary(&self, nodes: &[&Tree], writer: &mut String) -> fmt::Result {
+        let mut summary = Summary::default();
+        for node in nodes {
+            summary.add(node);
+        }
+
+        write!(
+            writer,
+            "... {}, {} hidden",
+            Plural(summary.spans, "span", "spans"),
+            Plural(summary.events, "event", "events"),
+        )?;
+
+        if self.durations && summary.spans > 0 {
+            let duration: Duration = nodes
+                .iter()
+                .filter_map(|node| node.span().ok())
+                .map(Span::total_duration)
+                .sum();
+            write!(
+                writer,
+                " [ {} ]",
+                DurationDisplay(duration.as_nanos() as f64)
+            )?;
+        }
+
+        writeln!(writer)
+    }
+}
+
+/// The narrowest that wrapped text is allowed to get.
+const MIN_WRAP_WIDTH: usize = 20;
+
+/// Writes `line` to `writer`, breaking it at spaces so that each part fits in
+/// `width` columns, and starting each new part with `continuation`.
+fn wrap_line(line: &str, width: usize, continuation: &str, writer: &mut String) {
+    let mut column = 0;
+
+    for (n, word) in line.split(' ').enumerate() {
+        let word_width = display_width(word);
+
+        if n > 0 {
+            if column > 0 && column + 1 + word_width > width {
+                writer.push('\n');
+                writer.push_str(continuation);
+                column = 0;
+            } else {
+                writer.push(' ');
+                column += 1;
+            }
+        }
+
+        writer.push_str(word);
+        column += word_width;
+    }
+}
+
+/// Returns the number of columns that `s` takes up in a terminal, ignoring
+/// ANSI escape sequences.
+///
+/// Wide characters like CJK and most emoji take up two columns, and combining
+/// and other zero-width characters take up none.
+fn display_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            width += c.width().unwrap_or(0);
+        }
+    }
+
+    width
+}
+
+/// Returns whether `tree` contains a span or event at least as severe as
+/// `level`.
+fn is_at_least(tree: &Tree, level: Level) -> bool {
+    match tree {
+        Tree::Event(event) => event.level() <= level,
+        Tree::Span(span) => {
+            span.level() <= level || span.nodes().iter().any(|node| is_at_least(node, level))
+        }
+    }
+}
+
+/// The number of spans and events in a collapsed subtree.
+#[derive(Default)]
+struct Summary {
+    spans: usize,
+    events: usize,
+}
+
+impl Summary {
+    fn add(&mut self, tree: &Tree) {
+        match tree {
+            Tree::Event(_) => self.events += 1,
+            Tree::Span(span) if span.should_render() => {
+                self.spans += 1;
+                for node in span.nodes() {
+                    self.add(node);
+                }
+            }
+            Tree::Span(_) => {}
+        }
+    }
+}
+
+/// Displays a count with the singular or plural form of a noun.
+struct Plural(usize, &'static str, &'static str);
+
+impl fmt::Display for Plural {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let noun = if self.0 == 1 { self.1 } else { self.2 };
+        write!(f, "{} {}", self.0, noun)
+    }
+}
+
+/// How the icon of each event's [`Tag`] is displayed.
+///
+/// Icons can be hidden entirely with [`Builder::icon`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IconMode {
+    /// The icon of the tag, which is typically emoji.
+    #[default]
+    Emoji,
+
+    /// A letter for the event's level instead, such as `E` for `ERROR`, for
+    /// terminals and log viewers that can't display emoji.
+    Text,
+}
+
+/// Returns the plain text displayed in place of an icon for `level`.
+fn level_text(level: Level) -> char {
+    match level {
+        Level::TRACE => 'T',
+        Level::DEBUG => 'D',
+        Level::INFO => 'I',
+        Level::WARN => 'W',
+        Level::ERROR => 'E',
+    }
+}
+
+/// The glyphs that a [`Pretty`] formatter draws the tree with.
+///
+/// Custom glyphs can be created with [`Glyphs::new`]. Each glyph should have
+/// the same width so that the levels of the tree line up.
+///
+/// # Examples
+///
+/// The tree drawn with each of the built-in glyph sets:
+/// ```txt
+/// HEAVY          LIGHT          ASCII
+/// my_span        my_span        my_span
+/// ┝━ first       ├─ first       |-- first
+/// │  ┕━ nested   │  └─ nested   |   `-- nested
+/// ┕━ second      └─ second      `-- second
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    blank: &'static str,
+    line: &'static str,
+    fork: &'static str,
+    turn: &'static str,
+}
+
+impl Glyphs {
+    /// Heavy Unicode box-drawing glyphs, which are the default.
+    pub const HEAVY: Glyphs = Glyphs::new("   ", "│  ", "┝━ ", "┕━ ");
+
+    /// Light Unicode box-drawing glyphs.
+    pub const LIGHT: Glyphs = Glyphs::new("   ", "│  ", "├─ ", "└─ ");
+
+    /// Plain ASCII glyphs, for terminals and log viewers that can't display
+    /// Unicode.
+    pub const ASCII: Glyphs = Glyphs::new("    ", "|   ", "|-- ", "`-- ");
+
+    /// Returns custom glyphs.
+    ///
+    /// * `blank` is drawn below the last child of a span.
+    /// * `line` is drawn below a child of a span that has more siblings.
+    /// * `fork` is drawn before a child of a span that has more siblings.
+    /// * `turn` is drawn before the last child of a span.
+    pub const fn new(
+        blank: &'static str,
+        line: &'static str,
+        fork: &'static str,
+        turn: &'static str,
+    ) -> Self {
+        Glyphs {
+            blank,
+            line,
+            fork,
+            turn,
+        }
+    }
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Glyphs::HEAVY
+    }
 }
 
 enum Indent {
@@ -219,17 +871,17 @@ enum Indent {
 }
 
 impl Indent {
-    fn repr(&self) -> &'static str {
+    fn repr(&self, glyphs: &Glyphs) -> &'static str {
         match self {
-            Self::Null => "   ",
-            Self::Line => "│  ",
-            Self::Fork => "┝━ ",
-            Self::Turn => "┕━ ",
+            Self::Null => glyphs.blank,
+            Self::Line => glyphs.line,
+            Self::Fork => glyphs.fork,
+            Self::Turn => glyphs.turn,
         }
     }
 }
 
-struct DurationDisplay(f64);
+pub(crate) struct DurationDisplay(pub(crate) f64);
 
 // Taken from chrono
 impl fmt::Display for DurationDisplay {
@@ -250,20 +902,21 @@ impl fmt::Display for DurationDisplay {
 }
 
 /// Implements colored formatting for a field if enabled
-struct FieldKey<'a>(&'a str);
+struct FieldKey<'a>(
+    &'a str,
+    #[cfg_attr(not(feature = "ansi"), allow(dead_code))] bool,
+);
 
 impl fmt::Display for FieldKey<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         #[cfg(feature = "ansi")]
-        {
+        if self.1 {
             let color = Color::White.dimmed();
 
-            write!(f, "{}{}{}", color.prefix(), self.0, color.suffix())
-        }
-        #[cfg(not(feature = "ansi"))]
-        {
-            f.write_str(self.0)
+            return write!(f, "{}{}{}", color.prefix(), self.0, color.suffix());
         }
+
+        f.write_str(self.0)
     }
 }
 
@@ -0,0 +1,119 @@
+use crate::printer::pretty::DurationDisplay;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, TimeZone, Timelike, Utc};
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// How timestamps are displayed.
+///
+/// This is used by [`Pretty`] and by events marked as `immediate`.
+///
+/// [`Pretty`]: crate::printer::Pretty
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum TimestampMode {
+    /// An RFC 3339 timestamp in UTC, such as `2022-03-24T16:08:17.761149+00:00`.
+    #[default]
+    Rfc3339,
+
+    /// An RFC 3339 timestamp in the local time zone.
+    Local,
+
+    /// A custom [`strftime`] pattern, rendered in UTC.
+    ///
+    /// [`strftime`]: chrono::format::strftime
+    Custom(Cow<'static, str>),
+
+    /// A custom [`strftime`] pattern, rendered in the local time zone.
+    ///
+    /// [`strftime`]: chrono::format::strftime
+    LocalCustom(Cow<'static, str>),
+
+    /// The time since the root span of the tree opened, such as `+1.52ms`.
+    SinceRoot,
+
+    /// The time since the previous line, such as `+310µs`.
+    SincePrevious,
+}
+
+impl TimestampMode {
+    /// The width that RFC 3339 timestamps are padded to.
+    pub(crate) const RFC3339_WIDTH: usize = 36;
+
+    /// Returns the width that the timestamp is padded to when columns are
+    /// aligned.
+    ///
+    /// Custom patterns are as wide as they format a reference timestamp whose
+    /// fields are as wide as they get, such as `Wednesday, 29 September`.
+    pub(crate) fn width(&self) -> usize {
+        let reference = || {
+            Utc.with_ymd_and_hms(2021, 9, 29, 23, 59, 59)
+                .unwrap()
+                .with_nanosecond(999_999_999)
+                .unwrap()
+        };
+
+        match self {
+            TimestampMode::Rfc3339 | TimestampMode::Local => TimestampMode::RFC3339_WIDTH,
+            TimestampMode::Custom(_) | TimestampMode::LocalCustom(_) => {
+                self.format(reference(), None, None).chars().count()
+            }
+            TimestampMode::SinceRoot | TimestampMode::SincePrevious => 10,
+        }
+    }
+
+    /// Panics if the mode has an invalid [`strftime`] pattern.
+    ///
+    /// [`strftime`]: chrono::format::strftime
+    pub(crate) fn validate(&self) {
+        if let TimestampMode::Custom(pattern) | TimestampMode::LocalCustom(pattern) = self {
+            assert!(
+                !StrftimeItems::new(pattern.as_ref()).any(|item| item == Item::Error),
+                "invalid strftime pattern for TimestampMode: {:?}",
+                pattern
+            );
+        }
+    }
+
+    /// Formats `timestamp`.
+    ///
+    /// If a custom pattern can't be formatted, the timestamp is formatted as
+    /// RFC 3339 instead.
+    ///
+    /// `root` and `previous` are the timestamps of the root of the tree and
+    /// of the previous line, if there are any.
+    pub(crate) fn format(
+        &self,
+        timestamp: DateTime<Utc>,
+        root: Option<DateTime<Utc>>,
+        previous: Option<DateTime<Utc>>,
+    ) -> String {
+        let since = |earlier: Option<DateTime<Utc>>| {
+            let elapsed = earlier
+                .and_then(|earlier| (timestamp - earlier).to_std().ok())
+                .unwrap_or_default();
+            format!("+{}", DurationDisplay(elapsed.as_nanos() as f64))
+        };
+
+        match self {
+            TimestampMode::Rfc3339 => timestamp.to_rfc3339(),
+            TimestampMode::Local => timestamp.with_timezone(&Local).to_rfc3339(),
+            TimestampMode::Custom(pattern) => {
+                let mut string = String::new();
+                match write!(string, "{}", timestamp.format(pattern.as_ref())) {
+                    Ok(()) => string,
+                    Err(_) => timestamp.to_rfc3339(),
+                }
+            }
+            TimestampMode::LocalCustom(pattern) => {
+                let timestamp = timestamp.with_timezone(&Local);
+                let mut string = String::new();
+                match write!(string, "{}", timestamp.format(pattern.as_ref())) {
+                    Ok(()) => string,
+                    Err(_) => timestamp.to_rfc3339(),
+                }
+            }
+            TimestampMode::SinceRoot => since(root),
+            TimestampMode::SincePrevious => since(previous),
+        }
+    }
+}
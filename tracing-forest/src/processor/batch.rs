@@ -0,0 +1,267 @@
+//! Buffer trees and process them in batches.
+//!
+//! Writing every `Tree` with its own system call can be slow for file and socket
+//! destinations under high volume. [`Batch`] is a [`Processor`] that buffers
+//! incoming trees and hands them to a [`BatchProcessor`] once enough trees or
+//! enough time has built up.
+//!
+//! # Examples
+//!
+//! Writing to a file in batches of up to 128 trees, writing each `Tree` within
+//! 100 milliseconds.
+//! ```no_run
+//! use tracing_forest::processor::batch::Batch;
+//! use tracing_forest::Printer;
+//! use std::fs::File;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let out = File::create("out.log").unwrap();
+//!
+//! tracing_forest::worker_task()
+//!     .map_receiver(|printer| Batch::new(printer.writer(out))
+//!         .max_trees(128)
+//!         .max_delay(Duration::from_millis(100))
+//!     )
+//!     .build()
+//!     .on(async {
+//!         // ...
+//!     })
+//!     .await;
+//! # }
+//! ```
+//!
+//! [`Processor`]: crate::Processor
+use crate::processor::{self, FlushResult, Processor};
+use crate::tree::Tree;
+use std::error;
+use std::mem;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Error type returned if a [`BatchProcessor`] fails.
+#[derive(Error, Debug)]
+#[error("{source}")]
+pub struct Error {
+    /// The recoverable trees that couldn't be processed.
+    pub trees: Vec<Tree>,
+
+    source: Box<dyn error::Error + Send + Sync>,
+}
+
+/// Create an error for when a [`BatchProcessor`] fails to process trees.
+pub fn error(trees: Vec<Tree>, source: Box<dyn error::Error + Send + Sync>) -> Error {
+    Error { trees, source }
+}
+
+/// The result type of [`BatchProcessor::process_batch`].
+pub type Result = std::result::Result<(), Error>;
+
+/// A trait for processing many completed [`Tree`]s at once.
+///
+/// This trait is implemented for [`Printer`], which writes the whole batch
+/// with a single write, and for all `Fn(Vec<Tree>) -> batch::Result` types.
+///
+/// [`Printer`]: crate::printer::Printer
+pub trait BatchProcessor: 'static {
+    /// Process a batch of trees, in the order they were received.
+    ///
+    /// # Errors
+    ///
+    /// If the trees cannot be processed, then they are returned along with a
+    /// `Box<dyn Error + Send + Sync>`.
+    fn process_batch(&self, trees: Vec<Tree>) -> Result;
+}
+
+impl<F> BatchProcessor for F
+where
+    F: 'static + Fn(Vec<Tree>) -> Result,
+{
+    fn process_batch(&self, trees: Vec<Tree>) -> Result {
+        self(trees)
+    }
+}
+
+/// A [`Processor`] that buffers trees and processes them in batches.
+///
+/// A batch is handed to the inner [`BatchProcessor`] once [`max_trees`] trees
+/// have been buffered, once a `Tree` arrives more than [`max_delay`] after the
+/// oldest buffered `Tree`, or when [`Processor::flush`] is called. The `Batch`
+/// reports when its oldest `Tree` is due through [`Processor::next_flush`], so
+/// a [`worker_task`] writes idle batches on time without a flush interval.
+/// Any remaining buffered trees are processed when the `Batch` is dropped.
+///
+/// # Errors
+///
+/// If the inner processor fails while processing a batch, the `Tree` that
+/// completed the batch is returned in the error so that it can be handed to a
+/// fallback. The rest of the failed trees aren't retried, but are kept along
+/// with the error and returned by the next call to [`Processor::flush`], which
+/// a [`WithFallback`] hands to its fallback processor. At most [`max_trees`]
+/// failed trees are kept, and older ones are dropped to make room for newer
+/// ones. If the inner processor doesn't return any trees, its error is also
+/// returned by the next flush. Failed trees that haven't been returned by a
+/// flush when the `Batch` is dropped are dropped with it.
+///
+/// Only a `WithFallback` wrapping the `Batch` directly recovers the failed
+/// trees from a flush. Behind a [`Tee`], a [`Router`] or a
+/// `Box<dyn Processor>`, the error is still returned, but its trees aren't
+/// handed to a fallback.
+///
+/// See the [module-level documentation](self) for more details.
+///
+/// [`max_trees`]: Batch::max_trees
+/// [`max_delay`]: Batch::max_delay
+/// [`worker_task`]: crate::runtime::worker_task
+/// [`WithFallback`]: crate::processor::WithFallback
+/// [`Tee`]: crate::processor::Tee
+/// [`Router`]: crate::processor::router::Router
+#[derive(Debug)]
+pub struct Batch<P: BatchProcessor> {
+    processor: P,
+    max_trees: usize,
+    max_delay: Duration,
+    buffer: Mutex<Buffer>,
+}
+
+#[derive(Debug, Default)]
+struct Buffer {
+    trees: Vec<Tree>,
+    oldest: Option<Instant>,
+    /// The error of the last failed batch, and the trees that failed.
+    failed: Option<Error>,
+}
+
+impl<P: BatchProcessor> Batch<P> {
+    /// Returns a new `Batch` that processes batches of up to 64 trees, which
+    /// are completed after at most a second.
+    pub fn new(processor: P) -> Self {
+        Batch {
+            processor,
+            max_trees: 64,
+            max_delay: Duration::from_secs(1),
+            buffer: Mutex::default(),
+        }
+    }
+
+    /// Set the number of trees that completes a batch.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max_trees` is zero.
+    pub fn max_trees(mut self, max_trees: usize) -> Self {
+        assert!(max_trees > 0, "`max_trees` must be greater than zero");
+        self.max_trees = max_trees;
+        self
+    }
+
+    /// Set the amount of time after which an incoming `Tree` completes a batch.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Hands the buffered trees to the inner processor.
+    fn flush_buffer(&self, buffer: &mut Buffer) -> Result {
+        if buffer.trees.is_empty() {
+            return Ok(());
+        }
+
+        let trees = mem::take(&mut buffer.trees);
+        buffer.oldest = None;
+
+        self.processor.process_batch(trees)
+    }
+
+    /// Keeps the trees of a failed batch to be returned by the next flush.
+    fn keep_failed(&self, buffer: &mut Buffer, err: Error) {
+        let mut trees = match buffer.failed.take() {
+            Some(failed) => failed.trees,
+            None => Vec::new(),
+        };
+        trees.extend(err.trees);
+
+        let excess = trees.len().saturating_sub(self.max_trees);
+        trees.drain(..excess);
+
+        buffer.failed = Some(Error {
+            trees,
+            source: err.source,
+        });
+    }
+}
+
+impl<P: BatchProcessor> Processor for Batch<P> {
+    fn process(&self, tree: Tree) -> processor::Result {
+        let mut buffer = self.lock();
+
+        let oldest = *buffer.oldest.get_or_insert_with(Instant::now);
+        buffer.trees.push(tree);
+
+        if buffer.trees.len() < self.max_trees && oldest.elapsed() < self.max_delay {
+            return Ok(());
+        }
+
+        match self.flush_buffer(&mut buffer) {
+            Ok(()) => Ok(()),
+            Err(Error { mut trees, source }) => match trees.pop() {
+                Some(tree) => {
+                    if !trees.is_empty() {
+                        let source = source.to_string().into();
+                        self.keep_failed(&mut buffer, Error { trees, source });
+                    }
+                    Err(processor::error(tree, source))
+                }
+                None => {
+                    self.keep_failed(&mut buffer, Error { trees, source });
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    /// Processes the buffered trees.
+    ///
+    /// # Errors
+    ///
+    /// If the buffered trees or an earlier batch failed to be processed, then
+    /// a [`batch::Error`] is returned with the trees that failed.
+    ///
+    /// [`batch::Error`]: struct@Error
+    fn flush(&self) -> FlushResult {
+        let mut buffer = self.lock();
+
+        if let Err(err) = self.flush_buffer(&mut buffer) {
+            self.keep_failed(&mut buffer, err);
+        }
+
+        match buffer.failed.take() {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.lock().oldest.map(|oldest| oldest + self.max_delay)
+    }
+}
+
+impl<P: BatchProcessor> Drop for Batch<P> {
+    fn drop(&mut self) {
+        let buffer = self
+            .buffer
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Trees that already failed aren't retried against the same processor.
+        if !buffer.trees.is_empty() {
+            let _ = self.processor.process_batch(mem::take(&mut buffer.trees));
+        }
+    }
+}
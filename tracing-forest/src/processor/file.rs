@@ -0,0 +1,373 @@
+//! Write trees to files that are rotated by size and time.
+//!
+//! See [`RollingFile`] for more details.
+use crate::printer::{Formatter, Pretty};
+use crate::processor::{self, FlushResult, Processor};
+use crate::tree::Tree;
+use std::borrow::Cow;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How often a [`RollingFile`] starts a new file, regardless of its size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    /// Never start a new file because of time.
+    Never,
+
+    /// Start a new file every hour, on the hour in UTC.
+    Hourly,
+
+    /// Start a new file every day, at midnight UTC.
+    Daily,
+}
+
+/// A [`Processor`] that formats trees and appends them to files in a
+/// directory, starting new files as they grow or age.
+///
+/// Files are named after the configured prefix, followed by the UTC date for
+/// [`Rotation::Daily`] or date and hour for [`Rotation::Hourly`], and then a
+/// counter if the file was started because the previous one exceeded the
+/// [`max_size`]. For example, with the prefix `app.log`:
+/// ```txt
+/// app.log.2022-03-24
+/// app.log.2022-03-24.1
+/// app.log.2022-03-25
+/// ```
+///
+/// Each `Tree` is formatted up front and written with a single write, so a
+/// tree is never split across two files. This means that a file can exceed
+/// `max_size` if a single `Tree` is larger than it.
+///
+/// If [`max_files`] is set, the oldest files with the same prefix are deleted
+/// whenever a new file is started.
+///
+/// Files are opened lazily, and reopened after a write fails, so creating a
+/// `RollingFile` never fails. If the process restarts, writing continues in
+/// the most recent file for the current period.
+///
+/// # Examples
+///
+/// Writing to a new file every day or every 10 MiB, and keeping the last
+/// week's worth of files.
+/// ```no_run
+/// use tracing_forest::processor::file::{RollingFile, Rotation};
+/// use tracing_forest::{traits::*, util::*};
+/// use tracing_subscriber::Registry;
+///
+/// let processor = RollingFile::new("/var/log/my-app", "app.log")
+///     .rotation(Rotation::Daily)
+///     .max_size(10 * 1024 * 1024)
+///     .max_files(7);
+///
+/// Registry::default()
+///     .with(ForestLayer::from(processor))
+///     .init();
+/// ```
+///
+/// [`max_size`]: RollingFile::max_size
+/// [`max_files`]: RollingFile::max_files
+#[derive(Debug)]
+pub struct RollingFile<F = Pretty> {
+    formatter: F,
+    directory: PathBuf,
+    prefix: String,
+    rotation: Rotation,
+    max_size: Option<u64>,
+    max_files: Option<usize>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    file: Option<File>,
+    /// The period the open file belongs to.
+    period: u64,
+    /// The counter of the open file within its period.
+    index: u32,
+    /// The number of bytes in the open file.
+    size: u64,
+}
+
+impl RollingFile<Pretty> {
+    /// Returns a `RollingFile` that pretty-prints trees to files in
+    /// `directory` whose names start with `prefix`.
+    ///
+    /// By default, files are never rotated.
+    pub fn new(directory: impl AsRef<Path>, prefix: impl Into<String>) -> Self {
+        RollingFile {
+            formatter: Pretty::new(),
+            directory: directory.as_ref().to_path_buf(),
+            prefix: prefix.into(),
+            rotation: Rotation::Never,
+            max_size: None,
+            max_files: None,
+            state: Mutex::new(State::default()),
+        }
+    }
+}
+
+impl<F: Formatter> RollingFile<F> {
+    /// Set the formatter.
+    ///
+    /// See the [`Formatter`] trait for details on possible inputs.
+    pub fn formatter<F2: Formatter>(self, formatter: F2) -> RollingFile<F2> {
+        RollingFile {
+            formatter,
+            directory: self.directory,
+            prefix: self.prefix,
+            rotation: self.rotation,
+            max_size: self.max_size,
+            max_files: self.max_files,
+            state: self.state,
+        }
+    }
+
+    /// Set how often a new file is started.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Start a new file instead of growing the current one beyond `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is zero.
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        assert!(bytes > 0, "max_size must be nonzero");
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Delete the oldest files once there are more than `files` of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `files` is zero.
+    pub fn max_files(mut self, files: usize) -> Self {
+        assert!(files > 0, "max_files must be nonzero");
+        self.max_files = Some(files);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the period that `now` falls in.
+    fn period(&self, now: SystemTime) -> u64 {
+        let secs = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+
+        match self.rotation {
+            Rotation::Never => 0,
+            Rotation::Hourly => secs / 3600,
+            Rotation::Daily => secs / 86400,
+        }
+    }
+
+    /// Returns the name of every file in a period, without its counter.
+    fn base_name(&self, period: u64) -> String {
+        match self.rotation {
+            Rotation::Never => self.prefix.clone(),
+            Rotation::Hourly => {
+                let (year, month, day) = civil_from_days(period / 24);
+                let hour = period % 24;
+                format!("{}.{year:04}-{month:02}-{day:02}-{hour:02}", self.prefix)
+            }
+            Rotation::Daily => {
+                let (year, month, day) = civil_from_days(period);
+                format!("{}.{year:04}-{month:02}-{day:02}", self.prefix)
+            }
+        }
+    }
+
+    fn file_name(&self, period: u64, index: u32) -> String {
+        match index {
+            0 => self.base_name(period),
+            index => format!("{}.{index}", self.base_name(period)),
+        }
+    }
+
+    /// Returns the highest counter of the existing files in a period.
+    fn last_index(&self, period: u64) -> io::Result<u32> {
+        let base = self.base_name(period);
+        let mut last = 0;
+
+        for entry in fs::read_dir(&self.directory)? {
+            let name = entry?.file_name();
+            let index = match name.to_str().and_then(|name| name.strip_prefix(&*base)) {
+                Some("") => 0,
+                Some(rest) => match rest.strip_prefix('.').and_then(|i| i.parse().ok()) {
+                    Some(index) => index,
+                    None => continue,
+                },
+                None => continue,
+            };
+            last = last.max(index);
+        }
+
+        Ok(last)
+    }
+
+    /// Opens the file for `state.period` and `state.index` for appending.
+    fn open(&self, state: &mut State) -> io::Result<()> {
+        let path = self
+            .directory
+            .join(self.file_name(state.period, state.index));
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        state.size = file.metadata()?.len();
+        state.file = Some(file);
+
+        if let Some(max_files) = self.max_files {
+            // Failing to delete old files shouldn't stop trees from being
+            // written, so this is best-effort.
+            let _ = self.prune(max_files, &path);
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the oldest files named by this `RollingFile` until at most `max_files` are
+    /// left, never deleting the file at `current`.
+    fn prune(&self, max_files: usize, current: &Path) -> io::Result<()> {
+        let mut files = Vec::new();
+
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let is_ours = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&*self.prefix))
+                .is_some_and(is_rotation_suffix);
+
+            if is_ours && entry.path() != current && entry.file_type()?.is_file() {
+                files.push((entry.metadata()?.modified()?, name, entry.path()));
+            }
+        }
+
+        // The current file counts towards the limit.
+        if files.len() >= max_files {
+            files.sort();
+            for (_, _, path) in &files[..=files.len() - max_files] {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes `bytes` to the current file, starting a new one first if needed,
+    /// and writing `header` first if the file is empty.
+    fn write(&self, header: &str, bytes: &[u8]) -> io::Result<()> {
+        let mut state = self.lock();
+        let period = self.period(SystemTime::now());
+
+        if state.file.is_none() || state.period != period {
+            fs::create_dir_all(&self.directory)?;
+            state.period = period;
+            state.index = self.last_index(period)?;
+            self.open(&mut state)?;
+        }
+
+        if let Some(max_size) = self.max_size {
+            if state.size > 0 && state.size + bytes.len() as u64 > max_size {
+                state.index += 1;
+                self.open(&mut state)?;
+            }
+        }
+
+        // The header is written with the first tree, so that a new file is
+        // never left with only a header.
+        let bytes = if state.size == 0 && !header.is_empty() {
+            Cow::Owned([header.as_bytes(), bytes].concat())
+        } else {
+            Cow::Borrowed(bytes)
+        };
+        let file = state.file.as_mut().expect("file was just opened");
+        if let Err(err) = file.write_all(&bytes) {
+            state.file = None;
+            return Err(err);
+        }
+        state.size += bytes.len() as u64;
+
+        Ok(())
+    }
+}
+
+impl<F: 'static + Formatter> Processor for RollingFile<F> {
+    fn process(&self, tree: Tree) -> processor::Result {
+        let string = match self.formatter.fmt(&tree) {
+            Ok(s) => s,
+            Err(e) => return Err(processor::error(tree, e.into())),
+        };
+
+        match self.write(self.formatter.header(), string.as_bytes()) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(processor::error(tree, e.into())),
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        match &mut self.lock().file {
+            Some(file) => Ok(file.flush()?),
+            None => Ok(()),
+        }
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        match self.lock().file.take() {
+            Some(file) => Ok(file.sync_all()?),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns whether `rest`, which follows the prefix in a file name, is empty
+/// or one of the suffixes that a `RollingFile` adds: a date, optionally with
+/// an hour, then a counter, each optional and starting with a `.`.
+fn is_rotation_suffix(rest: &str) -> bool {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let is_date = |s: &str| {
+        let parts: Vec<_> = s.split('-').collect();
+        matches!(parts.len(), 3 | 4)
+            && parts
+                .iter()
+                .zip([4, 2, 2, 2])
+                .all(|(part, len)| part.len() == len && is_digits(part))
+    };
+
+    let mut parts = rest.split('.');
+    if parts.next() != Some("") {
+        return false;
+    }
+    match (parts.next(), parts.next(), parts.next()) {
+        (None, _, _) => true,
+        (Some(counter), None, _) if is_digits(counter) => true,
+        (Some(date), None, _) => is_date(date),
+        (Some(date), Some(counter), None) => is_date(date) && is_digits(counter),
+        _ => false,
+    }
+}
+
+/// Converts days since the Unix epoch to a `(year, month, day)` date.
+///
+/// Credit: <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
@@ -0,0 +1,115 @@
+//! Combine the folded stacks of many trees into one flame graph.
+//!
+//! See [`FoldedStacks`] for more details.
+use crate::printer::folded::{fold, write_stacks};
+use crate::printer::MakeStdout;
+use crate::processor::{self, FlushResult, Processor};
+use crate::tree::Tree;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// A [`Processor`] that adds up the folded stacks of every `Tree`, and writes
+/// them when flushed or shut down.
+///
+/// This is like a [`Printer`] with the [`Folded`] formatter, except that
+/// identical stacks from different trees are combined, so the output is
+/// smaller. The stacks are cleared once they have been written.
+///
+/// # Examples
+///
+/// Writing a flame graph of a whole program's span time when it exits.
+/// ```no_run
+/// use std::fs::File;
+/// use tracing_forest::processor::folded::FoldedStacks;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let out = File::create("stacks.folded").unwrap();
+///
+/// tracing_forest::worker_task()
+///     .set_global(true)
+///     .map_receiver(|_| FoldedStacks::new().writer(out))
+///     .build()
+///     .on(async {
+///         // ...
+///     })
+///     .await;
+/// # }
+/// ```
+/// Afterwards, `inferno-flamegraph stacks.folded > flamegraph.svg` draws the
+/// flame graph.
+///
+/// [`Printer`]: crate::Printer
+/// [`Folded`]: crate::printer::Folded
+#[derive(Debug)]
+pub struct FoldedStacks<W = MakeStdout> {
+    make_writer: W,
+    stacks: Mutex<BTreeMap<String, u128>>,
+}
+
+impl FoldedStacks {
+    /// Returns a `FoldedStacks` that writes to stdout.
+    pub fn new() -> Self {
+        FoldedStacks {
+            make_writer: MakeStdout,
+            stacks: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl Default for FoldedStacks {
+    fn default() -> Self {
+        FoldedStacks::new()
+    }
+}
+
+impl<W> FoldedStacks<W>
+where
+    W: 'static + for<'a> MakeWriter<'a>,
+{
+    /// Set the writer.
+    pub fn writer<W2>(self, make_writer: W2) -> FoldedStacks<W2>
+    where
+        W2: 'static + for<'a> MakeWriter<'a>,
+    {
+        FoldedStacks {
+            make_writer,
+            stacks: self.stacks,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, u128>> {
+        self.stacks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W> Processor for FoldedStacks<W>
+where
+    W: 'static + for<'a> MakeWriter<'a>,
+{
+    fn process(&self, tree: Tree) -> processor::Result {
+        fold(&tree, &mut String::new(), &mut self.lock());
+        Ok(())
+    }
+
+    fn flush(&self) -> FlushResult {
+        let mut stacks = self.lock();
+        if stacks.is_empty() {
+            return Ok(());
+        }
+
+        let string = write_stacks(&stacks)?;
+        let mut writer = self.make_writer.make_writer();
+        writer.write_all(string.as_bytes())?;
+        writer.flush()?;
+        stacks.clear();
+
+        Ok(())
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        self.flush()
+    }
+}
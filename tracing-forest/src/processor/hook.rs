@@ -0,0 +1,160 @@
+//! Observe when a [`WithFallback`] processor resorts to its fallback, or when
+//! flushing or shutting down a processor fails.
+//!
+//! By default, [`WithFallback`] reports each failure of its primary processor
+//! to stderr with the [`Stderr`] hook. This can be replaced with any [`Hook`],
+//! such as [`Silent`], [`Counter`], or a closure, using [`WithFallback::hook`].
+//! Flush and shutdown failures in a [`worker_task`] or a [`FlushGuard`] are
+//! reported to the hook set with [`Builder::hook`] or [`FlushGuard::hook`].
+//!
+//! # Examples
+//!
+//! Counting how many trees had to be written to stderr instead of a file.
+//! ```no_run
+//! use tracing_forest::processor::hook::Counter;
+//! use tracing_forest::{traits::*, util::*, PrettyPrinter};
+//! use tracing_subscriber::Registry;
+//! use std::fs::File;
+//!
+//! let out = File::create("out.log").unwrap();
+//! let failures = Counter::new();
+//!
+//! let processor = PrettyPrinter::new()
+//!     .writer(out)
+//!     .or_stderr()
+//!     .hook(failures.clone());
+//!
+//! Registry::default()
+//!     .with(ForestLayer::from(processor))
+//!     .init();
+//!
+//! // -- snip --
+//!
+//! println!("{} trees were written to stderr", failures.count());
+//! ```
+//!
+//! [`WithFallback`]: crate::processor::WithFallback
+//! [`WithFallback::hook`]: crate::processor::WithFallback::hook
+//! [`worker_task`]: crate::runtime::worker_task
+//! [`Builder::hook`]: crate::runtime::Builder::hook
+//! [`FlushGuard`]: crate::processor::FlushGuard
+//! [`FlushGuard::hook`]: crate::processor::FlushGuard::hook
+use crate::fail;
+use crate::processor::Error;
+use std::error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A trait for observing failures that cause a [`WithFallback`] processor to
+/// resort to its fallback.
+///
+/// The hook is called with the primary processor's [`Error`] before the
+/// `Tree` it contains is handed to the fallback. The cause of the failure is
+/// available through [`std::error::Error::source`], and the `Tree` through
+/// [`Error::tree`].
+///
+/// This trait is blanket-implemented for all `Fn(&processor::Error)` types.
+///
+/// Flush and shutdown failures are ignored unless the hook overrides
+/// [`on_flush_error`] and [`on_shutdown_error`], as [`Stderr`] and [`Counter`]
+/// do.
+///
+/// [`WithFallback`]: crate::processor::WithFallback
+/// [`Error`]: struct@Error
+/// [`on_flush_error`]: Hook::on_flush_error
+/// [`on_shutdown_error`]: Hook::on_shutdown_error
+pub trait Hook: 'static {
+    /// Called when the primary processor fails.
+    fn on_fallback(&self, err: &Error);
+
+    /// Called when flushing a processor fails.
+    ///
+    /// The default implementation does nothing.
+    fn on_flush_error(&self, err: &dyn error::Error) {
+        let _ = err;
+    }
+
+    /// Called when shutting down a processor fails.
+    ///
+    /// The default implementation does nothing.
+    fn on_shutdown_error(&self, err: &dyn error::Error) {
+        let _ = err;
+    }
+}
+
+impl<F> Hook for F
+where
+    F: 'static + Fn(&Error),
+{
+    fn on_fallback(&self, err: &Error) {
+        self(err)
+    }
+}
+
+/// A [`Hook`] that ignores failures.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Silent;
+
+impl Hook for Silent {
+    fn on_fallback(&self, _err: &Error) {}
+}
+
+/// A [`Hook`] that reports failures to stderr.
+///
+/// This is the default hook for [`WithFallback`], [`worker_task`], and
+/// [`FlushGuard`].
+///
+/// [`WithFallback`]: crate::processor::WithFallback
+/// [`worker_task`]: crate::runtime::worker_task
+/// [`FlushGuard`]: crate::processor::FlushGuard
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stderr;
+
+impl Hook for Stderr {
+    fn on_fallback(&self, err: &Error) {
+        eprintln!("{err}, using fallback processor...");
+    }
+
+    fn on_flush_error(&self, err: &dyn error::Error) {
+        eprintln!("{}: {err}", fail::FLUSHING_ERROR);
+    }
+
+    fn on_shutdown_error(&self, err: &dyn error::Error) {
+        eprintln!("{}: {err}", fail::SHUTDOWN_ERROR);
+    }
+}
+
+/// A [`Hook`] that counts failures.
+///
+/// Flush and shutdown failures are counted too.
+///
+/// Clones of a `Counter` share the same count, so a clone can be kept to
+/// inspect the count after the original is given to a processor.
+#[derive(Clone, Debug, Default)]
+pub struct Counter(Arc<AtomicUsize>);
+
+impl Counter {
+    /// Returns a new `Counter` starting at zero.
+    pub fn new() -> Self {
+        Counter::default()
+    }
+
+    /// Returns the number of failures counted so far.
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Hook for Counter {
+    fn on_fallback(&self, _err: &Error) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_flush_error(&self, _err: &dyn error::Error) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_shutdown_error(&self, _err: &dyn error::Error) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
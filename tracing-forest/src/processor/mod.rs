@@ -0,0 +1,1235 @@
+//! Trait for processing log trees on completion.
+//!
+//! See [`Processor`] for more details, or [`AsyncProcessor`] for processing
+//! log trees asynchronously within a [`worker_task`].
+//!
+//! [`worker_task`]: crate::runtime::worker_task
+use crate::cfg_json;
+use crate::printer::{MakeStderr, MakeStdout, Pretty, Printer};
+use crate::tree::Tree;
+use std::error;
+use std::future::{self, Future};
+use std::pin::pin;
+use std::sync::Arc;
+#[cfg(feature = "tokio")]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Instant;
+use thiserror::Error;
+
+pub mod batch;
+pub mod file;
+pub mod folded;
+pub mod hook;
+pub mod rate_limit;
+pub mod retry;
+pub mod ring;
+pub mod router;
+
+cfg_json! {
+    pub mod socket;
+}
+
+/// Error type returned if a [`Processor`] fails.
+#[derive(Error, Debug)]
+#[error("{source}")]
+pub struct Error {
+    /// The recoverable [`Tree`] type that couldn't be processed.
+    pub tree: Tree,
+
+    source: Box<dyn error::Error + Send + Sync>,
+}
+
+/// Create an error for when a [`Processor`] fails to process a [`Tree`].
+pub fn error(tree: Tree, source: Box<dyn error::Error + Send + Sync>) -> Error {
+    Error { tree, source }
+}
+
+/// The result type of [`Processor::process`].
+pub type Result = std::result::Result<(), Error>;
+
+/// The result type of [`Processor::flush`] and [`Processor::shutdown`].
+pub type FlushResult = std::result::Result<(), Box<dyn error::Error + Send + Sync>>;
+
+/// A trait for processing completed [`Tree`]s.
+///
+/// `Processor`s are responsible for both formatting and writing logs to their
+/// intended destinations. This is typically implemented using
+/// [`Formatter`], [`MakeWriter`], and [`io::Write`].
+///
+/// While this trait may be implemented on downstream types, [`from_fn`]
+/// provides a convenient interface for creating `Processor`s without having to
+/// explicitly define new types.
+///
+/// [trace trees]: crate::tree::Tree
+/// [`Formatter`]: crate::printer::Formatter
+/// [`MakeWriter`]: tracing_subscriber::fmt::MakeWriter
+/// [`io::Write`]: std::io::Write
+pub trait Processor: 'static + Sized {
+    /// Process a [`Tree`]. This can mean many things, such as writing to
+    /// stdout or a file, sending over a network, storing in memory, ignoring,
+    /// or anything else.
+    ///
+    /// # Errors
+    ///
+    /// If the `Tree` cannot be processed, then it is returned along with a
+    /// `Box<dyn Error + Send + Sync>`. If the processor is configured with a
+    /// fallback processor from [`Processor::or`], then the `Tree` is deferred
+    /// to that processor.
+    // `Error` carries the `Tree` it failed on so that it can be recovered.
+    #[allow(clippy::result_large_err)]
+    fn process(&self, tree: Tree) -> Result;
+
+    /// Flush any trees that the processor has buffered.
+    ///
+    /// A [`worker_task`] calls this once it has finished processing every
+    /// `Tree`, and periodically if configured with [`flush_interval`]. Outside
+    /// of a `worker_task`, a [`FlushGuard`] from [`Processor::flush_guard`]
+    /// calls it when dropped.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// If buffered trees cannot be flushed, an error is returned. If the error
+    /// is a [`batch::Error`], then [`WithFallback`] hands the trees it contains
+    /// to its fallback processor. A [`worker_task`] reports errors to stderr
+    /// and keeps running.
+    ///
+    /// [`worker_task`]: crate::runtime::worker_task
+    /// [`flush_interval`]: crate::runtime::Builder::flush_interval
+    fn flush(&self) -> FlushResult {
+        Ok(())
+    }
+
+    /// Returns when the processor next needs to be [flushed][Processor::flush],
+    /// if it's holding trees that should be flushed by a deadline.
+    ///
+    /// A [`worker_task`] checks this after processing each `Tree` and flushes
+    /// the processor once the deadline passes, so processors like [`Batch`]
+    /// write idle trees without a [`flush_interval`]. Wrappers return the
+    /// earliest deadline of the processors they contain.
+    ///
+    /// The default implementation returns `None`.
+    ///
+    /// [`worker_task`]: crate::runtime::worker_task
+    /// [`Batch`]: batch::Batch
+    /// [`flush_interval`]: crate::runtime::Builder::flush_interval
+    fn next_flush(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Release any resources held by the processor, such as files or
+    /// connections.
+    ///
+    /// A [`worker_task`] or [`FlushGuard`] calls this exactly once, after the
+    /// final [`flush`][Processor::flush].
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// If the processor cannot be shut down cleanly, an error is returned.
+    ///
+    /// [`worker_task`]: crate::runtime::worker_task
+    fn shutdown(&self) -> FlushResult {
+        Ok(())
+    }
+
+    /// Returns a `Processor` that first attempts processing with `self`, and
+    /// resorts to processing with `fallback` on failure.
+    ///
+    /// Note that [`or_stdout`], [`or_stderr`], and [`or_none`] can be used as
+    /// shortcuts for pretty printing or dropping the `Tree` entirely.
+    ///
+    /// Each failure of `self` is reported to stderr by default. This can be
+    /// changed with [`WithFallback::hook`].
+    ///
+    /// [`or_stdout`]: Processor::or_stdout
+    /// [`or_stderr`]: Processor::or_stderr
+    /// [`or_none`]: Processor::or_none
+    fn or<P: Processor>(self, processor: P) -> WithFallback<Self, P> {
+        WithFallback {
+            primary: self,
+            fallback: processor,
+            hook: hook::Stderr,
+        }
+    }
+
+    /// Returns a `Processor` that first attempts processing with `self`, and
+    /// resorts to pretty-printing to stdout on failure.
+    fn or_stdout(self) -> WithFallback<Self, Printer<Pretty, MakeStdout>> {
+        self.or(Printer::new().writer(MakeStdout))
+    }
+
+    /// Returns a `Processor` that first attempts processing with `self`, and
+    /// resorts to pretty-printing to stderr on failure.
+    fn or_stderr(self) -> WithFallback<Self, Printer<Pretty, MakeStderr>> {
+        self.or(Printer::new().writer(MakeStderr))
+    }
+
+    /// Returns a `Processor` that first attempts processing with `self`, otherwise
+    /// silently fails.
+    fn or_none(self) -> WithFallback<Self, Sink> {
+        self.or(Sink)
+    }
+
+    /// Returns a `Processor` that gives each `Tree` to both `self` and `other`.
+    ///
+    /// `self` receives a clone of the `Tree`, and `other` receives the original.
+    /// Both processors always run, even if `self` fails. If exactly one of them
+    /// fails, its error is returned unchanged. If both fail, a [`TeeError`]
+    /// containing both sources is returned.
+    ///
+    /// Either way, at most one `Tree` is returned in the error. This means that
+    /// a fallback added to the result with [`Processor::or`] will process the
+    /// `Tree` once, even if it was successfully processed by the other branch.
+    /// To give each branch its own fallback, add the fallbacks before combining.
+    ///
+    /// # Examples
+    ///
+    /// Pretty-printing to stdout while also writing JSON to a file.
+    /// ```no_run
+    /// use tracing_forest::{traits::*, util::*, Printer, PrettyPrinter};
+    /// use tracing_subscriber::Registry;
+    /// use std::fs::File;
+    ///
+    /// let file = File::create("out.json").unwrap();
+    ///
+    /// let processor = PrettyPrinter::new().and(
+    ///     Printer::new()
+    ///         .formatter(serde_json::to_string)
+    ///         .writer(file)
+    ///         .or_stderr(),
+    /// );
+    ///
+    /// Registry::default()
+    ///     .with(ForestLayer::from(processor))
+    ///     .init();
+    /// ```
+    fn and<P: Processor>(self, other: P) -> Tee<Self, P> {
+        Tee {
+            first: self,
+            second: other,
+        }
+    }
+
+    /// Returns a `Processor` that only processes a `Tree` with `self` if
+    /// `predicate` returns `true`, and otherwise drops it.
+    ///
+    /// Dropped trees are considered successfully processed, so they never
+    /// reach a fallback.
+    ///
+    /// # Examples
+    ///
+    /// Only writing spans that took longer than a millisecond.
+    /// ```
+    /// use tracing_forest::traits::*;
+    /// use tracing_forest::tree::Tree;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// tracing_forest::worker_task()
+    ///     .map_receiver(|printer| printer.filter(|tree: &Tree| match tree {
+    ///         Tree::Span(span) => span.total_duration() > Duration::from_millis(1),
+    ///         Tree::Event(_) => true,
+    ///     }))
+    ///     .build()
+    ///     .on(async {
+    ///         // ...
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    fn filter<F>(self, predicate: F) -> Filter<Self, F>
+    where
+        F: 'static + Fn(&Tree) -> bool,
+    {
+        Filter {
+            processor: self,
+            f: predicate,
+        }
+    }
+
+    /// Returns a `Processor` that transforms each `Tree` with `f` before
+    /// processing it with `self`.
+    ///
+    /// If `self` fails, the transformed `Tree` is what is returned in the error.
+    ///
+    /// # Examples
+    ///
+    /// Writing only the children of root spans named `"request"`.
+    /// ```
+    /// use tracing_forest::traits::*;
+    /// use tracing_forest::tree::Tree;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// tracing_forest::worker_task()
+    ///     .map_receiver(|printer| printer
+    ///         .map(|tree| match tree {
+    ///             Tree::Span(span) if span.name() == "request" && span.nodes().len() == 1 => {
+    ///                 span.nodes()[0].clone()
+    ///             }
+    ///             tree => tree,
+    ///         })
+    ///     )
+    ///     .build()
+    ///     .on(async {
+    ///         // ...
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    fn map<F>(self, f: F) -> Map<Self, F>
+    where
+        F: 'static + Fn(Tree) -> Tree,
+    {
+        Map { processor: self, f }
+    }
+
+    /// Returns a `Processor` that transforms each `Tree` with `f`, processing
+    /// the result with `self` if it is `Some`, and otherwise dropping it.
+    ///
+    /// This is a combination of [`Processor::filter`] and [`Processor::map`].
+    fn filter_map<F>(self, f: F) -> FilterMap<Self, F>
+    where
+        F: 'static + Fn(Tree) -> Option<Tree>,
+    {
+        FilterMap { processor: self, f }
+    }
+
+    /// Returns a `Processor` that retries processing with `self` according to
+    /// the [retry policy], sleeping the current thread between attempts.
+    ///
+    /// If every attempt fails, the error from the last attempt is returned. This
+    /// means that a fallback added to the result with [`Processor::or`] is only
+    /// used once retries have run out, whereas adding a fallback to `self` before
+    /// calling `retry` would prevent any retries.
+    ///
+    /// Since sleeping blocks the thread, [`AsyncProcessor::retry_async`] should
+    /// be preferred within a [`worker_task`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tracing_forest::processor::retry::Policy;
+    /// use tracing_forest::{traits::*, util::*, Printer};
+    /// use tracing_subscriber::Registry;
+    /// use std::net::TcpStream;
+    ///
+    /// let stream = TcpStream::connect("127.0.0.1:9000").unwrap();
+    /// let processor = Printer::new()
+    ///     .writer(move || stream.try_clone().unwrap())
+    ///     .retry(Policy::new(3))
+    ///     .or_stderr();
+    ///
+    /// Registry::default()
+    ///     .with(ForestLayer::from(processor))
+    ///     .init();
+    /// ```
+    ///
+    /// [retry policy]: retry::Policy
+    /// [`worker_task`]: crate::runtime::worker_task
+    fn retry(self, policy: retry::Policy) -> retry::Retry<Self> {
+        retry::Retry::new(self, policy)
+    }
+
+    /// Returns a shared handle to `self`, and a [`FlushGuard`] that flushes
+    /// and shuts down `self` when it's dropped.
+    ///
+    /// A `ForestLayer` installed with [`init`] is never dropped, so without a
+    /// [`worker_task`], nothing else flushes trees that `self` has buffered.
+    /// The guard should be held until the end of `main`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tracing_forest::processor::file::RollingFile;
+    /// use tracing_forest::{traits::*, util::*};
+    /// use tracing_subscriber::Registry;
+    ///
+    /// let (processor, _guard) = RollingFile::new("/var/log/my-app", "app.log").flush_guard();
+    ///
+    /// Registry::default()
+    ///     .with(ForestLayer::from(processor))
+    ///     .init();
+    /// ```
+    ///
+    /// [`init`]: tracing_subscriber::util::SubscriberInitExt::init
+    /// [`worker_task`]: crate::runtime::worker_task
+    fn flush_guard(self) -> (Arc<Self>, FlushGuard<Self>) {
+        let processor = Arc::new(self);
+        let guard = FlushGuard {
+            processor: Some(processor.clone()),
+            hook: hook::Stderr,
+        };
+        (processor, guard)
+    }
+
+    /// Returns an [`AsyncProcessor`] that processes each `Tree` with `self` on
+    /// Tokio's blocking thread pool.
+    ///
+    /// This is useful for processors that perform slow, blocking I/O, since it
+    /// prevents them from blocking the executor thread of a [`worker_task`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use tracing_forest::traits::*;
+    /// use std::fs::File;
+    ///
+    /// let out = File::create("out.log").unwrap();
+    ///
+    /// tracing_forest::worker_task()
+    ///     .map_receiver(|printer| printer.writer(out).spawn_blocking())
+    ///     .build()
+    ///     .on(async {
+    ///         // ...
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    /// [`worker_task`]: crate::runtime::worker_task
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    fn spawn_blocking(self) -> SpawnBlocking<Self>
+    where
+        Self: Send + Sync,
+    {
+        SpawnBlocking(Arc::new(self))
+    }
+}
+
+/// A trait for processing completed [`Tree`]s asynchronously.
+///
+/// This is the trait required of the processor in a [`worker_task`], which
+/// awaits each `Tree` before receiving the next. Every [`Processor`] that is
+/// `Send` is also an `AsyncProcessor` that processes trees inline, and
+/// [`Processor::spawn_blocking`] can be used to move slow processing off of the
+/// worker task's executor thread. Conversely, [`AsyncProcessor::block_on`]
+/// turns an `AsyncProcessor` into a `Processor`.
+///
+/// The methods of this trait end in `_async` so that they can be called on a
+/// `Processor` without being ambiguous.
+///
+/// # Examples
+///
+/// Writing trees to a Tokio channel, waiting for capacity if it's full.
+/// ```
+/// use tracing_forest::processor::{self, AsyncProcessor};
+/// use tracing_forest::tree::Tree;
+/// use std::future::Future;
+/// use tokio::sync::mpsc;
+///
+/// struct Forward(mpsc::Sender<Tree>);
+///
+/// impl AsyncProcessor for Forward {
+///     fn process_async(&self, tree: Tree) -> impl Future<Output = processor::Result> + Send {
+///         async move {
+///             self.0.send(tree).await.map_err(|err| {
+///                 let msg = err.to_string().into();
+///                 processor::error(err.0, msg)
+///             })
+///         }
+///     }
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let (tx, mut rx) = mpsc::channel(64);
+///
+/// tracing_forest::worker_task()
+///     .set_global(false)
+///     .map_receiver(|_printer| Forward(tx))
+///     .build()
+///     .on(async {
+///         tracing::info!("hello");
+///     })
+///     .await;
+///
+/// assert!(rx.recv().await.is_some());
+/// # }
+/// ```
+///
+/// [`worker_task`]: crate::runtime::worker_task
+pub trait AsyncProcessor: 'static + Sized + Send {
+    /// Process a [`Tree`] asynchronously.
+    ///
+    /// # Errors
+    ///
+    /// If the `Tree` cannot be processed, then it is returned along with a
+    /// `Box<dyn Error + Send + Sync>`, the same as [`Processor::process`].
+    fn process_async(&self, tree: Tree) -> impl Future<Output = Result> + Send;
+
+    /// Flush any trees that the processor has buffered.
+    ///
+    /// This is the asynchronous analog of [`Processor::flush`], and the default
+    /// implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// If buffered trees cannot be flushed, an error is returned.
+    fn flush_async(&self) -> impl Future<Output = FlushResult> + Send {
+        future::ready(Ok(()))
+    }
+
+    /// Returns when the processor next needs to be flushed.
+    ///
+    /// This is the analog of [`Processor::next_flush`], and the default
+    /// implementation returns `None`.
+    fn next_flush_async(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Release any resources held by the processor.
+    ///
+    /// This is the asynchronous analog of [`Processor::shutdown`], and the
+    /// default implementation does nothing.
+    ///
+    /// # Errors
+    ///
+    /// If the processor cannot be shut down cleanly, an error is returned.
+    fn shutdown_async(&self) -> impl Future<Output = FlushResult> + Send {
+        future::ready(Ok(()))
+    }
+
+    /// Returns a [`Processor`] that processes each `Tree` by blocking the
+    /// current thread until `self` finishes processing it.
+    ///
+    /// # Note
+    ///
+    /// The returned processor parks the calling thread while waiting, so the
+    /// future returned by `self` must not rely on being polled by an executor
+    /// running on that same thread.
+    fn block_on(self) -> BlockOn<Self> {
+        BlockOn(self)
+    }
+
+    /// Returns an `AsyncProcessor` that retries processing with `self`
+    /// according to the [retry policy], asynchronously sleeping between attempts.
+    ///
+    /// This is the asynchronous analog of [`Processor::retry`], and is suited
+    /// to processors in a [`worker_task`]. Like with `Processor::retry`, a
+    /// fallback added to the result with [`or_async`] is only used once
+    /// retries have run out.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tracing_forest::processor::{retry::Policy, AsyncProcessor};
+    /// use tracing_forest::printer::MakeStderr;
+    /// use tracing_forest::Printer;
+    /// use std::fs::File;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let out = File::create("out.log").unwrap();
+    ///
+    /// tracing_forest::worker_task()
+    ///     .map_receiver(|printer| {
+    ///         printer
+    ///             .writer(out)
+    ///             .retry_async(Policy::new(3))
+    ///             .or_async(Printer::new().writer(MakeStderr))
+    ///     })
+    ///     .build()
+    ///     .on(async {
+    ///         // ...
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    /// [retry policy]: retry::Policy
+    /// [`worker_task`]: crate::runtime::worker_task
+    /// [`or_async`]: AsyncProcessor::or_async
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    fn retry_async(self, policy: retry::Policy) -> retry::AsyncRetry<Self>
+    where
+        Self: Sync,
+    {
+        retry::AsyncRetry::new(self, policy)
+    }
+
+    /// Returns an `AsyncProcessor` that first attempts processing with `self`,
+    /// and resorts to processing with `fallback` on failure.
+    ///
+    /// This is the asynchronous analog of [`Processor::or`]. Each failure of
+    /// `self` is reported to stderr by default. This can be changed with
+    /// [`AsyncWithFallback::hook`].
+    fn or_async<P: AsyncProcessor>(self, fallback: P) -> AsyncWithFallback<Self, P> {
+        AsyncWithFallback {
+            primary: self,
+            fallback,
+            hook: hook::Stderr,
+        }
+    }
+}
+
+/// A [`Processor`] composed of a primary and a fallback `Processor`.
+///
+/// This type is returned by [`Processor::or`].
+///
+/// When the primary processor's flush fails with a [`batch::Error`], its trees
+/// are handed to the fallback, and the ones the fallback also rejects are
+/// returned in a new `batch::Error`. Errors of any other type are returned as is,
+/// so a [`Batch`] behind a [`Tee`], a [`Router`] or a `Box<dyn Processor>`
+/// returns its failed trees in the error instead.
+///
+/// [`batch::Error`]: struct@batch::Error
+/// [`Batch`]: batch::Batch
+/// [`Router`]: router::Router
+#[derive(Debug)]
+pub struct WithFallback<P, F, H = hook::Stderr> {
+    primary: P,
+    fallback: F,
+    hook: H,
+}
+
+impl<P, F, H> WithFallback<P, F, H>
+where
+    P: Processor,
+    F: Processor,
+    H: hook::Hook,
+{
+    /// Set the [`Hook`] that is notified each time the primary processor
+    /// fails, before the `Tree` is handed to the fallback.
+    ///
+    /// See the [`hook` module documentation](hook) for more details.
+    ///
+    /// [`Hook`]: hook::Hook
+    pub fn hook<H2: hook::Hook>(self, hook: H2) -> WithFallback<P, F, H2> {
+        WithFallback {
+            primary: self.primary,
+            fallback: self.fallback,
+            hook,
+        }
+    }
+
+    /// Hands the trees of a failed flush to the fallback processor.
+    ///
+    /// Every tree is handed over even if the fallback fails, and the ones it
+    /// rejects are returned in a [`batch::Error`].
+    ///
+    /// [`batch::Error`]: struct@batch::Error
+    fn fall_back(&self, err: batch::Error) -> FlushResult {
+        if err.trees.is_empty() {
+            return Err(err.into());
+        }
+
+        let message = err.to_string();
+        let mut rejected = Rejected::default();
+        for tree in err.trees {
+            let err = error(tree, message.clone().into());
+            self.hook.on_fallback(&err);
+            rejected.push(self.fallback.process(err.tree));
+        }
+
+        rejected.into_result()
+    }
+}
+
+/// An [`AsyncProcessor`] composed of a primary and a fallback `AsyncProcessor`.
+///
+/// This type is returned by [`AsyncProcessor::or_async`]. Like
+/// [`WithFallback`], it only recovers the trees of a failed flush from a
+/// [`batch::Error`].
+///
+/// [`batch::Error`]: struct@batch::Error
+#[derive(Debug)]
+pub struct AsyncWithFallback<P, F, H = hook::Stderr> {
+    primary: P,
+    fallback: F,
+    hook: H,
+}
+
+impl<P, F, H> AsyncWithFallback<P, F, H>
+where
+    P: AsyncProcessor,
+    F: AsyncProcessor,
+    H: hook::Hook,
+{
+    /// Set the [`Hook`] that is notified each time the primary processor
+    /// fails, before the `Tree` is handed to the fallback.
+    ///
+    /// See the [`hook` module documentation](hook) for more details.
+    ///
+    /// [`Hook`]: hook::Hook
+    pub fn hook<H2: hook::Hook>(self, hook: H2) -> AsyncWithFallback<P, F, H2> {
+        AsyncWithFallback {
+            primary: self.primary,
+            fallback: self.fallback,
+            hook,
+        }
+    }
+}
+
+/// The trees that a fallback processor rejected while recovering a failed
+/// flush, along with the first of its errors.
+#[derive(Default)]
+struct Rejected {
+    trees: Vec<Tree>,
+    source: Option<Box<dyn error::Error + Send + Sync>>,
+}
+
+impl Rejected {
+    fn push(&mut self, result: Result) {
+        if let Err(err) = result {
+            self.trees.push(err.tree);
+            self.source.get_or_insert(err.source);
+        }
+    }
+
+    fn into_result(self) -> FlushResult {
+        match self.source {
+            Some(source) => Err(batch::error(self.trees, source).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A [`Processor`] that gives each `Tree` to two `Processor`s.
+///
+/// This type is returned by [`Processor::and`].
+#[derive(Debug)]
+pub struct Tee<P, Q> {
+    first: P,
+    second: Q,
+}
+
+/// Error type returned by [`Tee`] when both of its processors fail.
+#[derive(Error, Debug)]
+#[error("{first}, and {second}")]
+pub struct TeeError {
+    first: Box<dyn error::Error + Send + Sync>,
+    second: Box<dyn error::Error + Send + Sync>,
+}
+
+impl TeeError {
+    fn join(first: FlushResult, second: FlushResult) -> FlushResult {
+        match (first, second) {
+            (Ok(()), Ok(())) => Ok(()),
+            (Err(err), Ok(())) | (Ok(()), Err(err)) => Err(err),
+            (Err(first), Err(second)) => Err(TeeError { first, second }.into()),
+        }
+    }
+
+    /// Returns the error from the first processor.
+    pub fn first(&self) -> &(dyn error::Error + Send + Sync + 'static) {
+        self.first.as_ref()
+    }
+
+    /// Returns the error from the second processor.
+    pub fn second(&self) -> &(dyn error::Error + Send + Sync + 'static) {
+        self.second.as_ref()
+    }
+}
+
+/// A [`Processor`] that only processes trees matching a predicate.
+///
+/// This type is returned by [`Processor::filter`].
+#[derive(Debug)]
+pub struct Filter<P, F> {
+    processor: P,
+    f: F,
+}
+
+/// A [`Processor`] that transforms trees before processing them.
+///
+/// This type is returned by [`Processor::map`].
+#[derive(Debug)]
+pub struct Map<P, F> {
+    processor: P,
+    f: F,
+}
+
+/// A [`Processor`] that transforms trees and processes them if present.
+///
+/// This type is returned by [`Processor::filter_map`].
+#[derive(Debug)]
+pub struct FilterMap<P, F> {
+    processor: P,
+    f: F,
+}
+
+/// An [`AsyncProcessor`] that processes trees on Tokio's blocking thread pool.
+///
+/// This type is returned by [`Processor::spawn_blocking`].
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+#[derive(Debug)]
+pub struct SpawnBlocking<P>(Arc<P>);
+
+/// A guard that flushes and shuts down a [`Processor`] when it's dropped.
+///
+/// Errors are reported to a [`Hook`], which reports them to stderr by default.
+/// This type is returned by [`Processor::flush_guard`].
+///
+/// [`Hook`]: hook::Hook
+#[derive(Debug)]
+#[must_use = "the processor is flushed as soon as the guard is dropped"]
+pub struct FlushGuard<P: Processor, H: hook::Hook = hook::Stderr> {
+    // Only `None` once the processor has been moved to a guard with another hook.
+    processor: Option<Arc<P>>,
+    hook: H,
+}
+
+impl<P: Processor, H: hook::Hook> FlushGuard<P, H> {
+    /// Set the [`Hook`] that is notified if flushing or shutting down the
+    /// processor fails.
+    ///
+    /// See the [`hook` module documentation](hook) for more details.
+    ///
+    /// [`Hook`]: hook::Hook
+    pub fn hook<H2: hook::Hook>(mut self, hook: H2) -> FlushGuard<P, H2> {
+        FlushGuard {
+            processor: self.processor.take(),
+            hook,
+        }
+    }
+}
+
+/// A [`Processor`] that blocks on an [`AsyncProcessor`].
+///
+/// This type is returned by [`AsyncProcessor::block_on`].
+#[derive(Debug)]
+pub struct BlockOn<P>(P);
+
+/// A [`Processor`] that ignores any incoming logs.
+///
+/// This processor cannot fail.
+#[derive(Debug)]
+pub struct Sink;
+
+/// A [`Processor`] that processes incoming logs via a function.
+///
+/// Instances of `FromFn` are returned by the [`from_fn`] function.
+#[derive(Debug)]
+pub struct FromFn<F>(F);
+
+/// Create a processor that processes incoming logs via a function.
+///
+/// # Examples
+///
+/// Internally, [`worker_task`] uses `from_fn` to allow the subscriber to send
+/// trace data across a channel to a processing task.
+/// ```
+/// use tokio::sync::mpsc;
+/// use tracing_forest::processor;
+///
+/// let (tx, rx) = mpsc::unbounded_channel();
+///
+/// let sender_processor = processor::from_fn(move |tree| tx
+///     .send(tree)
+///     .map_err(|err| {
+///         let msg = err.to_string().into();
+///         processor::error(err.0, msg)
+///     })
+/// );
+///
+/// // -- snip --
+/// ```
+///
+/// [`worker_task`]: crate::runtime::worker_task
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: 'static + Fn(Tree) -> Result,
+{
+    FromFn(f)
+}
+
+impl<P, F, H> Processor for WithFallback<P, F, H>
+where
+    P: Processor,
+    F: Processor,
+    H: hook::Hook,
+{
+    #[allow(clippy::result_large_err)]
+    fn process(&self, tree: Tree) -> Result {
+        self.primary.process(tree).or_else(|err| {
+            self.hook.on_fallback(&err);
+            self.fallback.process(err.tree)
+        })
+    }
+
+    fn flush(&self) -> FlushResult {
+        let primary = self.primary.flush().or_else(|err| match err.downcast() {
+            Ok(err) => self.fall_back(*err),
+            Err(err) => Err(err),
+        });
+        let fallback = self.fallback.flush();
+        primary.and(fallback)
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        earliest(self.primary.next_flush(), self.fallback.next_flush())
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        let primary = self.primary.shutdown();
+        let fallback = self.fallback.shutdown();
+        primary.and(fallback)
+    }
+}
+
+impl<P, F, H> AsyncProcessor for AsyncWithFallback<P, F, H>
+where
+    P: AsyncProcessor + Sync,
+    F: AsyncProcessor + Sync,
+    H: hook::Hook + Send + Sync,
+{
+    async fn process_async(&self, tree: Tree) -> Result {
+        match self.primary.process_async(tree).await {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                self.hook.on_fallback(&err);
+                self.fallback.process_async(err.tree).await
+            }
+        }
+    }
+
+    async fn flush_async(&self) -> FlushResult {
+        let primary = match self.primary.flush_async().await {
+            Ok(()) => Ok(()),
+            Err(err) => match err.downcast::<batch::Error>() {
+                Ok(err) if !err.trees.is_empty() => {
+                    let message = err.to_string();
+                    let mut rejected = Rejected::default();
+                    for tree in err.trees {
+                        let err = error(tree, message.clone().into());
+                        self.hook.on_fallback(&err);
+                        rejected.push(self.fallback.process_async(err.tree).await);
+                    }
+                    rejected.into_result()
+                }
+                Ok(err) => Err(err as Box<dyn error::Error + Send + Sync>),
+                Err(err) => Err(err),
+            },
+        };
+        let fallback = self.fallback.flush_async().await;
+        primary.and(fallback)
+    }
+
+    fn next_flush_async(&self) -> Option<Instant> {
+        earliest(
+            self.primary.next_flush_async(),
+            self.fallback.next_flush_async(),
+        )
+    }
+
+    async fn shutdown_async(&self) -> FlushResult {
+        let primary = self.primary.shutdown_async().await;
+        let fallback = self.fallback.shutdown_async().await;
+        primary.and(fallback)
+    }
+}
+
+impl<P, Q> Processor for Tee<P, Q>
+where
+    P: Processor,
+    Q: Processor,
+{
+    fn process(&self, tree: Tree) -> Result {
+        let first = self.first.process(tree.clone());
+        let second = self.second.process(tree);
+
+        match (first, second) {
+            (Ok(()), Ok(())) => Ok(()),
+            (Err(err), Ok(())) | (Ok(()), Err(err)) => Err(err),
+            (Err(first), Err(second)) => {
+                let source = TeeError {
+                    first: first.source,
+                    second: second.source,
+                };
+                Err(error(second.tree, source.into()))
+            }
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        TeeError::join(self.first.flush(), self.second.flush())
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        earliest(self.first.next_flush(), self.second.next_flush())
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        TeeError::join(self.first.shutdown(), self.second.shutdown())
+    }
+}
+
+impl<P, F> Processor for Filter<P, F>
+where
+    P: Processor,
+    F: 'static + Fn(&Tree) -> bool,
+{
+    fn process(&self, tree: Tree) -> Result {
+        if (self.f)(&tree) {
+            self.processor.process(tree)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        self.processor.flush()
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.processor.next_flush()
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        self.processor.shutdown()
+    }
+}
+
+impl<P, F> Processor for Map<P, F>
+where
+    P: Processor,
+    F: 'static + Fn(Tree) -> Tree,
+{
+    fn process(&self, tree: Tree) -> Result {
+        self.processor.process((self.f)(tree))
+    }
+
+    fn flush(&self) -> FlushResult {
+        self.processor.flush()
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.processor.next_flush()
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        self.processor.shutdown()
+    }
+}
+
+impl<P, F> Processor for FilterMap<P, F>
+where
+    P: Processor,
+    F: 'static + Fn(Tree) -> Option<Tree>,
+{
+    fn process(&self, tree: Tree) -> Result {
+        match (self.f)(tree) {
+            Some(tree) => self.processor.process(tree),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        self.processor.flush()
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.processor.next_flush()
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        self.processor.shutdown()
+    }
+}
+
+impl Processor for Sink {
+    fn process(&self, _tree: Tree) -> Result {
+        Ok(())
+    }
+}
+
+impl<F> Processor for FromFn<F>
+where
+    F: 'static + Fn(Tree) -> Result,
+{
+    fn process(&self, tree: Tree) -> Result {
+        (self.0)(tree)
+    }
+}
+
+impl<P: Processor + Send> AsyncProcessor for P {
+    fn process_async(&self, tree: Tree) -> impl Future<Output = Result> + Send {
+        future::ready(Processor::process(self, tree))
+    }
+
+    fn flush_async(&self) -> impl Future<Output = FlushResult> + Send {
+        future::ready(Processor::flush(self))
+    }
+
+    fn next_flush_async(&self) -> Option<Instant> {
+        Processor::next_flush(self)
+    }
+
+    fn shutdown_async(&self) -> impl Future<Output = FlushResult> + Send {
+        future::ready(Processor::shutdown(self))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<P: Processor + Send + Sync> AsyncProcessor for SpawnBlocking<P> {
+    #[allow(clippy::result_large_err)]
+    fn process_async(&self, tree: Tree) -> impl Future<Output = Result> + Send {
+        let processor = self.0.clone();
+        async move {
+            // The tree is only taken once the task runs, so it can be returned
+            // if the task is cancelled before then.
+            let slot = Arc::new(Mutex::new(Some(tree)));
+            let task_slot = slot.clone();
+
+            let task = spawn_blocking(move || {
+                let tree = lock(&task_slot).take().expect("Tree was already taken");
+                Processor::process(&*processor, tree)
+            });
+
+            match task.await {
+                Ok(result) => result,
+                Err(err) => match lock(&slot).take() {
+                    Some(tree) => Err(error(tree, err.into())),
+                    None => unreachable!("A blocking task can't be cancelled once it runs"),
+                },
+            }
+        }
+    }
+
+    fn flush_async(&self) -> impl Future<Output = FlushResult> + Send {
+        let processor = self.0.clone();
+        async move { spawn_blocking(move || Processor::flush(&*processor)).await? }
+    }
+
+    fn next_flush_async(&self) -> Option<Instant> {
+        Processor::next_flush(&*self.0)
+    }
+
+    fn shutdown_async(&self) -> impl Future<Output = FlushResult> + Send {
+        let processor = self.0.clone();
+        async move { spawn_blocking(move || Processor::shutdown(&*processor)).await? }
+    }
+}
+
+/// Returns the earlier of two flush deadlines.
+pub(crate) fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Runs `f` on Tokio's blocking thread pool, resuming any panic.
+///
+/// An error is only returned if the task was cancelled because the runtime is
+/// shutting down.
+#[cfg(feature = "tokio")]
+async fn spawn_blocking<T, F>(f: F) -> std::result::Result<T, tokio::task::JoinError>
+where
+    T: 'static + Send,
+    F: 'static + Send + FnOnce() -> T,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(output) => Ok(output),
+        Err(err) => match err.try_into_panic() {
+            Ok(payload) => std::panic::resume_unwind(payload),
+            Err(err) => Err(err),
+        },
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<P: AsyncProcessor> Processor for BlockOn<P> {
+    fn process(&self, tree: Tree) -> Result {
+        block_on(self.0.process_async(tree))
+    }
+
+    fn flush(&self) -> FlushResult {
+        block_on(self.0.flush_async())
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.0.next_flush_async()
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        block_on(self.0.shutdown_async())
+    }
+}
+
+/// Drives a future to completion by parking the current thread until woken.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unparker(Thread);
+
+    impl Wake for Unparker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unparker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+impl<P: Processor, H: hook::Hook> Drop for FlushGuard<P, H> {
+    fn drop(&mut self) {
+        if let Some(processor) = self.processor.take() {
+            if let Err(err) = processor.flush() {
+                self.hook.on_flush_error(&*err);
+            }
+            if let Err(err) = processor.shutdown() {
+                self.hook.on_shutdown_error(&*err);
+            }
+        }
+    }
+}
+
+impl<P: Processor> Processor for Box<P> {
+    fn process(&self, tree: Tree) -> Result {
+        self.as_ref().process(tree)
+    }
+
+    fn flush(&self) -> FlushResult {
+        self.as_ref().flush()
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.as_ref().next_flush()
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        self.as_ref().shutdown()
+    }
+}
+
+impl<P: Processor> Processor for Arc<P> {
+    fn process(&self, tree: Tree) -> Result {
+        self.as_ref().process(tree)
+    }
+
+    fn flush(&self) -> FlushResult {
+        self.as_ref().flush()
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.as_ref().next_flush()
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        self.as_ref().shutdown()
+    }
+}
@@ -0,0 +1,296 @@
+//! Limit the rate at which trees are processed.
+//!
+//! See [`RateLimit`] for more details.
+use crate::processor::{self, FlushResult, Processor, Sink};
+use crate::tree::{self, FieldSet, Tree};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::Level;
+
+/// Determines which trees share a rate limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// All trees share a single rate limit.
+    Global,
+
+    /// Trees share a rate limit with other trees whose root span has the same
+    /// name. Events at the root level share a single rate limit.
+    RootName,
+
+    /// Trees share a rate limit with other trees whose root has the same level.
+    Level,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Global,
+    RootName(Option<Cow<'static, str>>),
+    Level(Level),
+}
+
+impl Key {
+    fn of(self, tree: &Tree) -> BucketKey {
+        match self {
+            Key::Global => BucketKey::Global,
+            Key::RootName => BucketKey::RootName(match tree {
+                Tree::Event(_) => None,
+                Tree::Span(span) => Some(span.name.clone()),
+            }),
+            Key::Level => BucketKey::Level(match tree {
+                Tree::Event(event) => event.level(),
+                Tree::Span(span) => span.level(),
+            }),
+        }
+    }
+}
+
+/// A [`Processor`] that limits the rate at which trees are processed using a
+/// token bucket.
+///
+/// Each bucket holds up to [`burst`] tokens and is refilled at the configured
+/// rate. Processing a `Tree` takes one token from its bucket, and trees that
+/// arrive when their bucket is empty are handed to the [`overflow`] processor,
+/// which drops them by default. Trees can share a single bucket, or be given
+/// separate buckets by their root span name or level with [`key`].
+///
+/// Whenever trees are suppressed, a `WARN` event saying how many were
+/// suppressed is processed before the next `Tree` that is allowed through,
+/// at most once per [`summary_interval`]. Outstanding summaries are also
+/// processed when the `RateLimit` is [flushed](Processor::flush), subject to
+/// the same interval. A [`worker_task`] flushes the `RateLimit` once a summary
+/// is due, so suppressed trees are reported even if no more trees arrive.
+///
+/// # Examples
+///
+/// Allowing 100 trees per second for each root span name, with bursts of up
+/// to 500, and writing the rest to stderr.
+/// ```
+/// use tracing_forest::processor::rate_limit::{Key, RateLimit};
+/// use tracing_forest::{traits::*, util::*, PrettyPrinter, Printer};
+/// use tracing_forest::printer::MakeStderr;
+/// use tracing_subscriber::Registry;
+///
+/// let processor = RateLimit::new(PrettyPrinter::new(), 100)
+///     .burst(500)
+///     .key(Key::RootName)
+///     .overflow(Printer::new().writer(MakeStderr));
+///
+/// Registry::default()
+///     .with(ForestLayer::from(processor))
+///     .init();
+/// ```
+///
+/// [`burst`]: RateLimit::burst
+/// [`overflow`]: RateLimit::overflow
+/// [`key`]: RateLimit::key
+/// [`summary_interval`]: RateLimit::summary_interval
+/// [`worker_task`]: crate::runtime::worker_task
+#[derive(Debug)]
+pub struct RateLimit<P, O = Sink> {
+    processor: P,
+    overflow: O,
+    per_second: f64,
+    burst: f64,
+    key: Key,
+    summary_interval: Duration,
+    buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    suppressed: u64,
+    summarized: Instant,
+}
+
+impl Bucket {
+    fn new(tokens: f64, now: Instant) -> Self {
+        Bucket {
+            tokens,
+            refilled: now,
+            suppressed: 0,
+            summarized: now,
+        }
+    }
+
+    /// Takes a summary of the suppressed trees if there are any, and one
+    /// hasn't been taken within `interval`.
+    fn take_summary(&mut self, key: &BucketKey, now: Instant, interval: Duration) -> Option<Tree> {
+        if self.suppressed == 0 || now.duration_since(self.summarized) < interval {
+            return None;
+        }
+
+        let suppressed = std::mem::take(&mut self.suppressed);
+        self.summarized = now;
+
+        let mut fields = FieldSet::default();
+        fields.push(tree::Field::new("suppressed", suppressed.to_string()));
+        match key {
+            BucketKey::Global | BucketKey::RootName(None) => {}
+            BucketKey::RootName(Some(name)) => {
+                fields.push(tree::Field::new("name", name.to_string()));
+            }
+            BucketKey::Level(level) => {
+                fields.push(tree::Field::new("level", level.to_string()));
+            }
+        }
+
+        let message = format!("{suppressed} trees suppressed by rate limit");
+        let event = tree::Event::synthetic(Level::WARN, message, fields);
+        Some(Tree::Event(event))
+    }
+}
+
+impl<P: Processor> RateLimit<P> {
+    /// Returns a new `RateLimit` that allows `per_second` trees per second
+    /// through to `processor`, and drops the rest.
+    ///
+    /// By default, the burst size is `per_second`, all trees share a single
+    /// rate limit, and summaries are processed at most once per second.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `per_second` is zero.
+    pub fn new(processor: P, per_second: u32) -> Self {
+        assert!(per_second > 0, "`per_second` must be greater than zero");
+        RateLimit {
+            processor,
+            overflow: Sink,
+            per_second: f64::from(per_second),
+            burst: f64::from(per_second),
+            key: Key::Global,
+            summary_interval: Duration::from_secs(1),
+            buckets: Mutex::default(),
+        }
+    }
+}
+
+impl<P, O> RateLimit<P, O>
+where
+    P: Processor,
+    O: Processor,
+{
+    /// Set the number of trees that can be processed at once before the rate
+    /// limit applies.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `burst` is zero.
+    pub fn burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "`burst` must be greater than zero");
+        self.burst = f64::from(burst);
+        self
+    }
+
+    /// Set which trees share a rate limit.
+    pub fn key(mut self, key: Key) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Set the minimum time between summaries of suppressed trees for each
+    /// rate limit.
+    pub fn summary_interval(mut self, summary_interval: Duration) -> Self {
+        self.summary_interval = summary_interval;
+        self
+    }
+
+    /// Set the processor that trees over the rate limit are handed to.
+    pub fn overflow<O2: Processor>(self, overflow: O2) -> RateLimit<P, O2> {
+        RateLimit {
+            processor: self.processor,
+            overflow,
+            per_second: self.per_second,
+            burst: self.burst,
+            key: self.key,
+            summary_interval: self.summary_interval,
+            buckets: self.buckets,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<BucketKey, Bucket>> {
+        self.buckets.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Summaries are best-effort, so failing to process one isn't an error.
+    fn process_summary(&self, summary: Option<Tree>) {
+        if let Some(summary) = summary {
+            let _ = self.processor.process(summary);
+        }
+    }
+}
+
+impl<P, O> Processor for RateLimit<P, O>
+where
+    P: Processor,
+    O: Processor,
+{
+    fn process(&self, tree: Tree) -> processor::Result {
+        let key = self.key.of(&tree);
+        let now = Instant::now();
+
+        let (allowed, summary) = {
+            let mut buckets = self.lock();
+            let bucket = buckets
+                .entry(key.clone())
+                .or_insert_with(|| Bucket::new(self.burst, now));
+
+            let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.burst);
+            bucket.refilled = now;
+
+            if bucket.tokens >= 1.0 {
+                bucket.tokens -= 1.0;
+                let summary = bucket.take_summary(&key, now, self.summary_interval);
+                (true, summary)
+            } else {
+                bucket.suppressed += 1;
+                (false, None)
+            }
+        };
+
+        if allowed {
+            self.process_summary(summary);
+            self.processor.process(tree)
+        } else {
+            self.overflow.process(tree)
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        let now = Instant::now();
+        let summaries: Vec<_> = self
+            .lock()
+            .iter_mut()
+            .filter_map(|(key, bucket)| bucket.take_summary(key, now, self.summary_interval))
+            .collect();
+
+        for summary in summaries {
+            self.process_summary(Some(summary));
+        }
+
+        let processor = self.processor.flush();
+        let overflow = self.overflow.flush();
+        processor.and(overflow)
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        let summary = self
+            .lock()
+            .values()
+            .filter(|bucket| bucket.suppressed > 0)
+            .map(|bucket| bucket.summarized + self.summary_interval)
+            .min();
+        let inner = processor::earliest(self.processor.next_flush(), self.overflow.next_flush());
+        processor::earliest(summary, inner)
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        let processor = self.processor.shutdown();
+        let overflow = self.overflow.shutdown();
+        processor.and(overflow)
+    }
+}
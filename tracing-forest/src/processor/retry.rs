@@ -0,0 +1,182 @@
+//! Retry failed processing with exponential backoff.
+//!
+//! See [`Processor::retry`] for more details.
+//!
+//! [`Processor::retry`]: crate::Processor::retry
+use crate::cfg_tokio;
+use crate::processor::{self, FlushResult, Processor};
+use crate::tree::Tree;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Determines how many times processing is attempted, and how long to wait
+/// between attempts.
+///
+/// The wait after the `n`th failed attempt is `initial_backoff * multiplier^(n - 1)`,
+/// capped at `max_backoff`.
+///
+/// # Examples
+///
+/// ```
+/// use tracing_forest::processor::retry::Policy;
+/// use std::time::Duration;
+///
+/// // Attempt 5 times, waiting 50ms, 100ms, 200ms, then 250ms between attempts.
+/// let policy = Policy::new(5)
+///     .initial_backoff(Duration::from_millis(50))
+///     .multiplier(2)
+///     .max_backoff(Duration::from_millis(250));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    multiplier: u32,
+    max_backoff: Duration,
+}
+
+impl Policy {
+    /// Returns a new `Policy` that attempts processing at most `max_attempts`
+    /// times, including the first attempt.
+    ///
+    /// By default, the first backoff is 10 milliseconds, each subsequent backoff
+    /// doubles, and no backoff is longer than a second.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max_attempts` is zero.
+    pub const fn new(max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "`max_attempts` must be greater than zero");
+        Policy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(10),
+            multiplier: 2,
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// Set the time to wait after the first failed attempt.
+    pub const fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the factor that the backoff grows by after each failed attempt.
+    pub const fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set the longest time to wait between attempts.
+    pub const fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Returns the time to wait after the `attempt`th failed attempt.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(attempt - 1);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// A [`Processor`] that retries failed processing, sleeping the current thread
+/// between attempts.
+///
+/// This type is returned by [`Processor::retry`].
+#[derive(Debug)]
+pub struct Retry<P> {
+    processor: P,
+    policy: Policy,
+}
+
+impl<P> Retry<P> {
+    pub(crate) fn new(processor: P, policy: Policy) -> Self {
+        Retry { processor, policy }
+    }
+}
+
+impl<P: Processor> Processor for Retry<P> {
+    fn process(&self, tree: Tree) -> processor::Result {
+        let mut tree = tree;
+        let mut attempt = 1;
+
+        loop {
+            match self.processor.process(tree) {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt >= self.policy.max_attempts => return Err(err),
+                Err(err) => {
+                    thread::sleep(self.policy.backoff(attempt));
+                    tree = err.tree;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        self.processor.flush()
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        self.processor.next_flush()
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        self.processor.shutdown()
+    }
+}
+
+cfg_tokio! {
+    use crate::processor::AsyncProcessor;
+    use std::future::Future;
+
+    /// An [`AsyncProcessor`] that retries failed processing, asynchronously
+    /// sleeping between attempts.
+    ///
+    /// This type is returned by [`AsyncProcessor::retry_async`].
+    #[derive(Debug)]
+    pub struct AsyncRetry<P> {
+        processor: P,
+        policy: Policy,
+    }
+
+    impl<P> AsyncRetry<P> {
+        pub(crate) fn new(processor: P, policy: Policy) -> Self {
+            AsyncRetry { processor, policy }
+        }
+    }
+
+    impl<P: AsyncProcessor + Sync> AsyncProcessor for AsyncRetry<P> {
+        async fn process_async(&self, tree: Tree) -> processor::Result {
+            let mut tree = tree;
+            let mut attempt = 1;
+
+            loop {
+                match self.processor.process_async(tree).await {
+                    Ok(()) => return Ok(()),
+                    Err(err) if attempt >= self.policy.max_attempts => return Err(err),
+                    Err(err) => {
+                        tokio::time::sleep(self.policy.backoff(attempt)).await;
+                        tree = err.tree;
+                        attempt += 1;
+                    }
+                }
+            }
+        }
+
+        fn flush_async(&self) -> impl Future<Output = FlushResult> + Send {
+            self.processor.flush_async()
+        }
+
+        fn next_flush_async(&self) -> Option<Instant> {
+            self.processor.next_flush_async()
+        }
+
+        fn shutdown_async(&self) -> impl Future<Output = FlushResult> + Send {
+            self.processor.shutdown_async()
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Keep recent trees in memory so they can be processed on demand.
+//!
+//! See [`RingBuffer`] for more details.
+use crate::processor::{self, Processor};
+use crate::tree::Tree;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A [`Processor`] that keeps the most recent trees in memory.
+///
+/// The buffer holds at most `capacity` trees, discarding the oldest `Tree`
+/// when it is full. If configured with [`max_age`], trees are also discarded
+/// once they have been in the buffer for longer than that.
+///
+/// The buffered trees can be inspected or handed to another processor at any
+/// time through a [`Handle`], which is obtained with [`RingBuffer::handle`]
+/// and can be cloned and shared freely.
+///
+/// # Examples
+///
+/// Printing only warnings and errors, but dumping the trees from the last
+/// minute when something goes wrong.
+/// ```
+/// use std::time::Duration;
+/// use tracing::Level;
+/// use tracing_forest::processor::ring::RingBuffer;
+/// use tracing_forest::tree::Tree;
+/// use tracing_forest::{traits::*, util::*, PrettyPrinter};
+/// use tracing_subscriber::Registry;
+///
+/// let ring = RingBuffer::new(1024).max_age(Duration::from_secs(60));
+/// let handle = ring.handle();
+///
+/// let printer = PrettyPrinter::new().filter(|tree: &Tree| match tree {
+///     Tree::Event(event) => event.level() <= Level::WARN,
+///     Tree::Span(span) => span.level() <= Level::WARN,
+/// });
+///
+/// Registry::default()
+///     .with(ForestLayer::from(printer.and(ring)))
+///     .init();
+///
+/// debug!("some context");
+///
+/// // Later, in an error handler
+/// handle.dump(&PrettyPrinter::new()).unwrap();
+/// ```
+///
+/// [`max_age`]: RingBuffer::max_age
+#[derive(Debug)]
+pub struct RingBuffer {
+    shared: Arc<Shared>,
+}
+
+/// A cloneable handle to the trees held by a [`RingBuffer`].
+#[derive(Clone, Debug)]
+pub struct Handle {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    capacity: usize,
+    max_age: Option<Duration>,
+    trees: Mutex<VecDeque<(Instant, Tree)>>,
+}
+
+impl RingBuffer {
+    /// Returns a `RingBuffer` that holds at most `capacity` trees.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be nonzero");
+
+        RingBuffer {
+            shared: Arc::new(Shared {
+                capacity,
+                max_age: None,
+                trees: Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    /// Discard trees once they have been buffered for longer than `max_age`.
+    ///
+    /// By default, trees are only discarded to make room for newer ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`Handle`] has already been obtained.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        Arc::get_mut(&mut self.shared)
+            .expect("max_age must be set before obtaining a handle")
+            .max_age = Some(max_age);
+        self
+    }
+
+    /// Returns a [`Handle`] to the trees held by this `RingBuffer`.
+    pub fn handle(&self) -> Handle {
+        Handle {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Shared {
+    /// Locks the buffer and discards any trees that have expired.
+    fn lock(&self) -> MutexGuard<'_, VecDeque<(Instant, Tree)>> {
+        let mut trees = self.trees.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(max_age) = self.max_age {
+            let now = Instant::now();
+            while let Some((received, _)) = trees.front() {
+                if now.saturating_duration_since(*received) <= max_age {
+                    break;
+                }
+                trees.pop_front();
+            }
+        }
+
+        trees
+    }
+}
+
+impl Handle {
+    /// Processes every buffered `Tree` with `processor`, from oldest to newest,
+    /// removing them from the buffer.
+    ///
+    /// # Errors
+    ///
+    /// If `processor` fails, the failed `Tree` is returned in the error and
+    /// the trees that weren't processed yet are put back into the buffer.
+    #[allow(clippy::result_large_err)]
+    pub fn dump<P: Processor>(&self, processor: &P) -> processor::Result {
+        let mut trees = std::mem::take(&mut *self.shared.lock()).into_iter();
+
+        for (_, tree) in trees.by_ref() {
+            if let Err(err) = processor.process(tree) {
+                let mut buffer = self.shared.lock();
+                for (i, entry) in trees.enumerate() {
+                    buffer.insert(i, entry);
+                }
+                while buffer.len() > self.shared.capacity {
+                    buffer.pop_front();
+                }
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a copy of every buffered `Tree`, from oldest to newest, leaving
+    /// the buffer untouched.
+    pub fn snapshot(&self) -> Vec<Tree> {
+        self.shared
+            .lock()
+            .iter()
+            .map(|(_, tree)| tree.clone())
+            .collect()
+    }
+
+    /// Returns the number of buffered trees.
+    pub fn len(&self) -> usize {
+        self.shared.lock().len()
+    }
+
+    /// Returns `true` if no trees are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.shared.lock().is_empty()
+    }
+
+    /// Discards every buffered `Tree`.
+    pub fn clear(&self) {
+        self.shared.lock().clear();
+    }
+}
+
+impl Processor for RingBuffer {
+    fn process(&self, tree: Tree) -> processor::Result {
+        let mut trees = self.shared.lock();
+
+        if trees.len() == self.shared.capacity {
+            trees.pop_front();
+        }
+        trees.push_back((Instant::now(), tree));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! Send trees to different processors depending on their contents.
+//!
+//! See [`Router`] for more details.
+use crate::processor::{self, FlushResult, Processor, Sink};
+use crate::tree::Tree;
+use std::fmt;
+use std::time::Instant;
+use tracing::Level;
+
+/// A condition that a [`Router`] checks each `Tree` against.
+pub struct Rule(Box<dyn Fn(&Tree) -> bool + Send + Sync>);
+
+impl Rule {
+    /// Matches trees whose root is a span named `name`.
+    pub fn name(name: &'static str) -> Self {
+        Rule(Box::new(move |tree| match tree {
+            Tree::Span(span) => span.name() == name,
+            Tree::Event(_) => false,
+        }))
+    }
+
+    /// Matches trees containing a span or event that is at least as severe as
+    /// `level`.
+    ///
+    /// For example, `Rule::level(Level::WARN)` matches trees containing a
+    /// `WARN` or `ERROR` span or event.
+    pub fn level(level: Level) -> Self {
+        Rule(Box::new(move |tree| {
+            any_node(tree, &|node| match node {
+                Tree::Event(event) => event.level() <= level,
+                Tree::Span(span) => span.level() <= level,
+            })
+        }))
+    }
+
+    /// Matches trees containing an event whose [`Tag`] is `tag`, in the same
+    /// `prefix.suffix` form that it's displayed in.
+    ///
+    /// For example, `Rule::tag("security.critical")` matches events tagged
+    /// `security.critical`, but not `security.alert`. Use [`Rule::tag_prefix`]
+    /// to match every tag with a given prefix.
+    ///
+    /// [`Tag`]: crate::Tag
+    pub fn tag(tag: &'static str) -> Self {
+        Rule(Box::new(move |tree| {
+            any_node(tree, &|node| match node {
+                Tree::Event(event) => event.tag().is_some_and(|t| match t.prefix() {
+                    Some(prefix) => {
+                        tag.strip_prefix(prefix)
+                            .and_then(|suffix| suffix.strip_prefix('.'))
+                            == Some(t.suffix())
+                    }
+                    None => tag == t.suffix(),
+                }),
+                Tree::Span(_) => false,
+            })
+        }))
+    }
+
+    /// Matches trees containing an event whose [`Tag`] has the prefix `prefix`.
+    ///
+    /// [`Tag`]: crate::Tag
+    pub fn tag_prefix(prefix: &'static str) -> Self {
+        Rule(Box::new(move |tree| {
+            any_node(tree, &|node| match node {
+                Tree::Event(event) => event.tag().and_then(|tag| tag.prefix()) == Some(prefix),
+                Tree::Span(_) => false,
+            })
+        }))
+    }
+
+    /// Matches trees containing a span or event with a field `key` whose value
+    /// is `value`.
+    ///
+    /// Field values are recorded with their `Debug` implementation, which
+    /// quotes strings, so `value` also matches a string field whose `Debug`
+    /// representation is `value` quoted. This means that both
+    /// `Rule::field("user", "admin")` and `Rule::field("user", "\"admin\"")`
+    /// match `user = "admin"`.
+    pub fn field(key: &'static str, value: impl Into<String>) -> Self {
+        let value = value.into();
+        let quoted = format!("{value:?}");
+        Rule(Box::new(move |tree| {
+            any_node(tree, &|node| {
+                let fields = match node {
+                    Tree::Event(event) => event.fields(),
+                    Tree::Span(span) => span.fields(),
+                };
+                fields.iter().any(|field| {
+                    field.key() == key && (field.value() == value || field.value() == quoted)
+                })
+            })
+        }))
+    }
+
+    /// Matches trees for which `f` returns `true`.
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: 'static + Fn(&Tree) -> bool + Send + Sync,
+    {
+        Rule(Box::new(f))
+    }
+
+    fn matches(&self, tree: &Tree) -> bool {
+        (self.0)(tree)
+    }
+}
+
+impl fmt::Debug for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Rule")
+    }
+}
+
+/// Returns `true` if `f` returns `true` for `tree` or any of its descendants.
+fn any_node(tree: &Tree, f: &impl Fn(&Tree) -> bool) -> bool {
+    f(tree)
+        || match tree {
+            Tree::Span(span) => span.nodes().iter().any(|node| any_node(node, f)),
+            Tree::Event(_) => false,
+        }
+}
+
+/// An object-safe [`Processor`], since `Processor` itself requires `Sized`.
+trait DynProcessor: Send + Sync {
+    #[allow(clippy::result_large_err)]
+    fn process(&self, tree: Tree) -> processor::Result;
+    fn flush(&self) -> FlushResult;
+    fn next_flush(&self) -> Option<Instant>;
+    fn shutdown(&self) -> FlushResult;
+}
+
+impl<P: Processor + Send + Sync> DynProcessor for P {
+    fn process(&self, tree: Tree) -> processor::Result {
+        Processor::process(self, tree)
+    }
+
+    fn flush(&self) -> FlushResult {
+        Processor::flush(self)
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        Processor::next_flush(self)
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        Processor::shutdown(self)
+    }
+}
+
+struct Route {
+    rule: Rule,
+    processor: Box<dyn DynProcessor>,
+}
+
+/// A [`Processor`] that sends each `Tree` to the processor of the first
+/// [`Rule`] it matches, or to a default processor if it matches none.
+///
+/// Rules are checked in the order they were added with [`route`]. Trees that
+/// don't match any rule are dropped unless a processor is set with
+/// [`default_route`].
+///
+/// # Examples
+///
+/// Sending security events to an audit file, errors to stderr, and everything
+/// else to stdout.
+/// ```no_run
+/// use tracing_forest::printer::MakeStderr;
+/// use tracing_forest::processor::file::RollingFile;
+/// use tracing_forest::processor::router::{Router, Rule};
+/// use tracing_forest::{traits::*, util::*, PrettyPrinter, Printer};
+/// use tracing_subscriber::Registry;
+///
+/// let processor = Router::new()
+///     .route(Rule::tag_prefix("security"), RollingFile::new("/var/log/my-app", "audit"))
+///     .route(Rule::level(Level::ERROR), Printer::new().writer(MakeStderr))
+///     .default_route(PrettyPrinter::new());
+///
+/// Registry::default()
+///     .with(ForestLayer::from(processor))
+///     .init();
+/// ```
+///
+/// [`route`]: Router::route
+/// [`default_route`]: Router::default_route
+pub struct Router<D = Sink> {
+    routes: Vec<Route>,
+    default: D,
+}
+
+impl Router {
+    /// Returns a `Router` with no rules that drops every `Tree`.
+    pub fn new() -> Self {
+        Router {
+            routes: Vec::new(),
+            default: Sink,
+        }
+    }
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Router::new()
+    }
+}
+
+impl<D: Processor> Router<D> {
+    /// Send trees matching `rule` to `processor`, unless they match an earlier
+    /// rule.
+    pub fn route<P>(mut self, rule: Rule, processor: P) -> Self
+    where
+        P: Processor + Send + Sync,
+    {
+        self.routes.push(Route {
+            rule,
+            processor: Box::new(processor),
+        });
+        self
+    }
+
+    /// Send trees that don't match any rule to `processor`.
+    pub fn default_route<P: Processor>(self, processor: P) -> Router<P> {
+        Router {
+            routes: self.routes,
+            default: processor,
+        }
+    }
+}
+
+impl<D: fmt::Debug> fmt::Debug for Router<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("routes", &self.routes.len())
+            .field("default", &self.default)
+            .finish()
+    }
+}
+
+impl<D: Processor> Processor for Router<D> {
+    fn process(&self, tree: Tree) -> processor::Result {
+        match self.routes.iter().find(|route| route.rule.matches(&tree)) {
+            Some(route) => route.processor.process(tree),
+            None => self.default.process(tree),
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        let default = self.default.flush();
+        self.routes.iter().fold(default, |result, route| {
+            let flushed = route.processor.flush();
+            result.and(flushed)
+        })
+    }
+
+    fn next_flush(&self) -> Option<Instant> {
+        let default = self.default.next_flush();
+        self.routes.iter().fold(default, |next, route| {
+            processor::earliest(next, route.processor.next_flush())
+        })
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        let default = self.default.shutdown();
+        self.routes.iter().fold(default, |result, route| {
+            let shut_down = route.processor.shutdown();
+            result.and(shut_down)
+        })
+    }
+}
@@ -0,0 +1,351 @@
+//! Stream trees as newline-delimited JSON over a socket.
+//!
+//! See [`Socket`] for sending trees, and [`Reader`] for receiving them.
+use crate::processor::{self, FlushResult, Processor};
+use crate::tree::Tree;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A [`Processor`] that writes each `Tree` as a line of JSON to a TCP or Unix
+/// domain socket.
+///
+/// The connection is opened lazily when the first `Tree` is processed. If
+/// writing fails, the `Socket` reconnects and tries once more before failing,
+/// and if connecting fails, no further attempts are made until the
+/// [`reconnect_delay`] has passed. Trees that fail in the meantime, or that are
+/// processed on other threads while a connection is being opened, are returned
+/// as errors, so they can be handled by a fallback processor.
+///
+/// Note that a write can succeed even if the other end has gone away, so
+/// trees written just before the connection drops may be lost.
+///
+/// Trees can be read back with a [`Reader`].
+///
+/// # Examples
+///
+/// Sending trees to a collector on localhost, and printing them to stderr if
+/// it can't be reached.
+/// ```no_run
+/// use tracing_forest::processor::socket::Socket;
+/// use tracing_forest::{traits::*, util::*};
+/// use tracing_subscriber::Registry;
+///
+/// let processor = Socket::tcp("127.0.0.1:9000").unwrap().or_stderr();
+///
+/// Registry::default()
+///     .with(ForestLayer::from(processor))
+///     .init();
+/// ```
+///
+/// [`reconnect_delay`]: Socket::reconnect_delay
+#[derive(Debug)]
+pub struct Socket {
+    address: Address,
+    connect_timeout: Duration,
+    reconnect_delay: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+enum Address {
+    Tcp(Vec<SocketAddr>),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+#[derive(Debug)]
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+#[derive(Debug, Default)]
+struct State {
+    stream: Option<Stream>,
+    /// When connecting last failed, if it failed.
+    failed_at: Option<Instant>,
+    /// Whether a thread is connecting without holding the lock.
+    connecting: bool,
+}
+
+impl Address {
+    fn connect(&self, timeout: Duration) -> io::Result<Stream> {
+        match self {
+            Address::Tcp(addrs) => {
+                let mut last_err = None;
+                for addr in addrs {
+                    match TcpStream::connect_timeout(addr, timeout) {
+                        Ok(stream) => return Ok(Stream::Tcp(stream)),
+                        Err(err) => last_err = Some(err),
+                    }
+                }
+                Err(last_err.unwrap_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to")
+                }))
+            }
+            #[cfg(unix)]
+            Address::Unix(path) => UnixStream::connect(path).map(Stream::Unix),
+        }
+    }
+}
+
+impl Stream {
+    fn shutdown(&self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.shutdown(Shutdown::Both),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+impl Socket {
+    fn new(address: Address) -> Self {
+        Socket {
+            address,
+            connect_timeout: Duration::from_secs(1),
+            reconnect_delay: Duration::from_secs(1),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Returns a `Socket` that connects to a TCP listener at `addr`.
+    ///
+    /// # Errors
+    ///
+    /// If `addr` cannot be resolved, an error is returned.
+    pub fn tcp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let addrs = addr.to_socket_addrs()?.collect();
+        Ok(Socket::new(Address::Tcp(addrs)))
+    }
+
+    /// Returns a `Socket` that connects to a Unix domain socket at `path`.
+    #[cfg(unix)]
+    pub fn unix(path: impl AsRef<Path>) -> Self {
+        Socket::new(Address::Unix(path.as_ref().to_path_buf()))
+    }
+
+    /// Set how long to wait for a TCP connection to be established.
+    ///
+    /// The default is 1 second.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set how long to wait after failing to connect before trying again.
+    ///
+    /// The default is 1 second.
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn send(&self, line: &[u8]) -> io::Result<()> {
+        let mut state = self.lock();
+
+        if let Some(stream) = &mut state.stream {
+            match stream.write_all(line) {
+                Ok(()) => return Ok(()),
+                // The connection dropped, so reconnect and try again.
+                Err(_) => state.stream = None,
+            }
+        }
+
+        if state.connecting {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "waiting for another thread to connect",
+            ));
+        }
+        if let Some(failed_at) = state.failed_at {
+            if failed_at.elapsed() < self.reconnect_delay {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "waiting to reconnect",
+                ));
+            }
+        }
+
+        // Connecting can block for up to `connect_timeout`, so it's done
+        // without holding the lock, and other threads fail fast meanwhile.
+        state.connecting = true;
+        drop(state);
+        let connected = self.address.connect(self.connect_timeout);
+        let mut state = self.lock();
+        state.connecting = false;
+
+        let stream = match connected {
+            Ok(stream) => {
+                state.failed_at = None;
+                state.stream.insert(stream)
+            }
+            Err(err) => {
+                state.failed_at = Some(Instant::now());
+                return Err(err);
+            }
+        };
+
+        let result = stream.write_all(line);
+        if result.is_err() {
+            state.stream = None;
+        }
+        result
+    }
+}
+
+impl Processor for Socket {
+    fn process(&self, tree: Tree) -> processor::Result {
+        let mut line = match serde_json::to_vec(&tree) {
+            Ok(line) => line,
+            Err(e) => return Err(processor::error(tree, e.into())),
+        };
+        line.push(b'\n');
+
+        match self.send(&line) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(processor::error(tree, e.into())),
+        }
+    }
+
+    fn flush(&self) -> FlushResult {
+        match &mut self.lock().stream {
+            Some(stream) => Ok(stream.flush()?),
+            None => Ok(()),
+        }
+    }
+
+    fn shutdown(&self) -> FlushResult {
+        match self.lock().stream.take() {
+            Some(stream) => Ok(stream.shutdown()?),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An iterator that reads trees written by a [`Socket`].
+///
+/// Each line of the underlying reader is parsed as a `Tree`. Blank lines are
+/// skipped, as is an unterminated final line, which is left behind when a
+/// connection drops partway through writing a `Tree`.
+///
+/// Lines longer than [`max_line_length`] aren't buffered, so that a peer can't
+/// use up memory by never ending a line. Instead, an error of kind
+/// [`InvalidData`] is returned, after which the iterator ends.
+///
+/// Each distinct tag that is read is leaked once, since [`Tag`]s are made of
+/// `&'static str`s. At most 1 MiB is leaked this way over the life of the
+/// process, shared by every `Reader`, after which trees with new tags are
+/// returned as errors.
+///
+/// # Examples
+///
+/// Printing every `Tree` received on a TCP port.
+/// ```no_run
+/// use std::net::TcpListener;
+/// use tracing_forest::processor::socket::Reader;
+/// use tracing_forest::{PrettyPrinter, Processor};
+///
+/// let listener = TcpListener::bind("127.0.0.1:9000").unwrap();
+/// let printer = PrettyPrinter::new();
+///
+/// for stream in listener.incoming() {
+///     for tree in Reader::new(stream.unwrap()) {
+///         printer.process(tree.unwrap()).unwrap();
+///     }
+/// }
+/// ```
+///
+/// [`max_line_length`]: Reader::max_line_length
+/// [`InvalidData`]: io::ErrorKind::InvalidData
+/// [`Tag`]: crate::Tag
+#[derive(Debug)]
+pub struct Reader<R> {
+    reader: BufReader<R>,
+    line: Vec<u8>,
+    max_line_length: usize,
+    done: bool,
+}
+
+impl<R: Read> Reader<R> {
+    /// Returns a `Reader` that reads trees from `reader`.
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader: BufReader::new(reader),
+            line: Vec::new(),
+            max_line_length: 16 * 1024 * 1024,
+            done: false,
+        }
+    }
+
+    /// Set the maximum length of a line in bytes, not counting the newline.
+    ///
+    /// The default is 16 MiB.
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = io::Result<Tree>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Room for the newline.
+        let limit = self.max_line_length as u64 + 1;
+
+        while !self.done {
+            self.line.clear();
+
+            match (&mut self.reader).take(limit).read_until(b'\n', &mut self.line) {
+                Ok(0) => self.done = true,
+                Ok(_) if self.line.last() != Some(&b'\n') => {
+                    self.done = true;
+                    if self.line.len() as u64 == limit {
+                        let msg = format!("line exceeds {} bytes", self.max_line_length);
+                        return Some(Err(io::Error::new(io::ErrorKind::InvalidData, msg)));
+                    }
+                }
+                Ok(_) if self.line.iter().all(u8::is_ascii_whitespace) => continue,
+                Ok(_) => return Some(serde_json::from_slice(&self.line).map_err(io::Error::from)),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+}
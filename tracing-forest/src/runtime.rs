@@ -104,13 +104,17 @@
 use crate::fail;
 use crate::layer::ForestLayer;
 use crate::printer::PrettyPrinter;
-use crate::processor::{self, Processor, WithFallback};
+use crate::processor::{
+    self, hook, AsyncProcessor, Filter, FilterMap, Map, Processor, Tee, WithFallback,
+};
 use crate::tag::{NoTag, TagParser};
 use crate::tree::Tree;
-use std::future::Future;
+use std::future::{self, Future};
 use std::iter;
+use std::time::Duration;
 use tokio::sync::mpsc::{self, UnboundedReceiver};
 use tokio::sync::oneshot;
+use tokio::time::{self, Interval, MissedTickBehavior};
 use tracing::Subscriber;
 use tracing_subscriber::layer::{Layered, SubscriberExt as _};
 use tracing_subscriber::Registry;
@@ -132,7 +136,13 @@ use tracing_subscriber::Registry;
 /// [nonblocking-processing]: crate::runtime#nonblocking-log-processing-with-worker_task
 /// [`set_global`]: Builder::set_global
 pub fn worker_task() -> Builder<InnerSender<impl Processor>, WorkerTask<PrettyPrinter>, NoTag> {
-    worker_task_inner(WorkerTask(PrettyPrinter::new()), true)
+    worker_task_inner(
+        WorkerTask {
+            processor: PrettyPrinter::new(),
+            hook: hook::Stderr,
+        },
+        true,
+    )
 }
 
 /// Begins the configuration of a `ForestLayer` subscriber that sends log trees
@@ -155,6 +165,7 @@ pub fn capture() -> Builder<InnerSender<impl Processor>, Capture, NoTag> {
     worker_task_inner(Capture(()), false)
 }
 
+#[allow(clippy::result_large_err)]
 fn worker_task_inner<P>(
     worker_processor: P,
     is_global: bool,
@@ -174,6 +185,7 @@ fn worker_task_inner<P>(
         receiver: rx,
         tag: NoTag,
         is_global,
+        flush_interval: None,
     }
 }
 
@@ -188,6 +200,8 @@ fn worker_task_inner<P>(
 /// * Installing [globally][set_global].
 /// * Configuring the [internal sender][map_sender] with fallbacks.
 /// * Configuring the [processor][map_receiver] in the worker task.
+/// * Periodically [flushing][flush_interval] the processor in the worker task.
+/// * Reporting flush and shutdown failures to a [hook].
 ///
 /// To finish the `Runtime`, call the [`build`] method to compose the configured
 /// `ForestLayer` onto a [`Registry`]. Alternatively, the [`build_on`] method
@@ -199,6 +213,8 @@ fn worker_task_inner<P>(
 /// [set_global]: Builder::set_global
 /// [map_sender]: Builder::map_sender
 /// [map_receiver]: Builder::map_receiver
+/// [flush_interval]: Builder::flush_interval
+/// [hook]: Builder::hook
 /// [`build`]: Builder::build
 /// [`build_on`]: Builder::build_on
 pub struct Builder<Tx, Rx, T> {
@@ -207,13 +223,17 @@ pub struct Builder<Tx, Rx, T> {
     receiver: UnboundedReceiver<Tree>,
     tag: T,
     is_global: bool,
+    flush_interval: Option<Duration>,
 }
 
 /// A marker type indicating that trace data should be captured for later use.
 pub struct Capture(());
 
 /// A marker type indicating that trace data should be processed.
-pub struct WorkerTask<P>(P);
+pub struct WorkerTask<P, H = hook::Stderr> {
+    processor: P,
+    hook: H,
+}
 
 /// The [`Processor`] used within a `tracing-forest` subscriber for sending logs
 /// to a processing task.
@@ -226,6 +246,18 @@ impl<P: Processor> Processor for InnerSender<P> {
     fn process(&self, tree: Tree) -> processor::Result {
         self.0.process(tree)
     }
+
+    fn flush(&self) -> processor::FlushResult {
+        self.0.flush()
+    }
+
+    fn next_flush(&self) -> Option<std::time::Instant> {
+        self.0.next_flush()
+    }
+
+    fn shutdown(&self) -> processor::FlushResult {
+        self.0.shutdown()
+    }
 }
 
 mod sealed {
@@ -234,17 +266,28 @@ mod sealed {
 
 impl<P> sealed::Sealed for InnerSender<P> {}
 
-impl<P: sealed::Sealed, F> sealed::Sealed for WithFallback<P, F> {}
+impl<P: sealed::Sealed, F, H> sealed::Sealed for WithFallback<P, F, H> {}
+
+impl<P: sealed::Sealed, Q> sealed::Sealed for Tee<P, Q> {}
+
+impl<P: sealed::Sealed, F> sealed::Sealed for Filter<P, F> {}
+
+impl<P: sealed::Sealed, F> sealed::Sealed for Map<P, F> {}
 
-impl<Tx, P, T> Builder<Tx, WorkerTask<P>, T>
+impl<P: sealed::Sealed, F> sealed::Sealed for FilterMap<P, F> {}
+
+impl<Tx, P, H, T> Builder<Tx, WorkerTask<P, H>, T>
 where
-    P: Processor,
+    P: AsyncProcessor,
+    H: hook::Hook,
 {
     /// Configure the processor on the receiving end of the log channel.
     /// This is particularly useful for adding fallbacks.
     ///
-    /// This method accepts a closure that accepts the current [`Processor`] on the
-    /// worker task, and maps it to another [`Processor`].
+    /// This method accepts a closure that accepts the current processor on the
+    /// worker task, and maps it to another [`AsyncProcessor`]. Since every
+    /// `Send` [`Processor`] is an `AsyncProcessor`, this can return either.
+    /// Use [`Processor::spawn_blocking`] for processors that block on slow I/O.
     ///
     /// # Note
     ///
@@ -273,17 +316,100 @@ where
     ///     .await;
     /// # }
     /// ```
-    pub fn map_receiver<F, P2>(self, f: F) -> Builder<Tx, WorkerTask<P2>, T>
+    pub fn map_receiver<F, P2>(self, f: F) -> Builder<Tx, WorkerTask<P2, H>, T>
     where
         F: FnOnce(P) -> P2,
-        P2: Processor,
+        P2: AsyncProcessor,
     {
         Builder {
             sender_processor: self.sender_processor,
-            worker_processor: WorkerTask(f(self.worker_processor.0)),
+            worker_processor: WorkerTask {
+                processor: f(self.worker_processor.processor),
+                hook: self.worker_processor.hook,
+            },
             receiver: self.receiver,
             tag: self.tag,
             is_global: self.is_global,
+            flush_interval: self.flush_interval,
+        }
+    }
+
+    /// Set how often the worker task flushes its processor while idle.
+    ///
+    /// By default, the processor is only flushed when the deadline reported by
+    /// [`Processor::next_flush`] passes, as it does for a [`Batch`], and once
+    /// every `Tree` has been processed at the end of [`Runtime::on`]. This is
+    /// useful for processors that buffer trees without reporting a deadline,
+    /// to ensure that buffered trees are still written during quiet periods.
+    /// If flushing fails, the error is reported to the [hook] and the worker
+    /// task keeps running.
+    ///
+    /// # Note
+    ///
+    /// This method is only available if called after [`worker_task`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// tracing_forest::worker_task()
+    ///     .flush_interval(Duration::from_millis(500))
+    ///     .build()
+    ///     .on(async {
+    ///         // ...
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    /// [`Batch`]: crate::processor::batch::Batch
+    /// [hook]: Builder::hook
+    pub fn flush_interval(mut self, period: Duration) -> Self {
+        self.flush_interval = Some(period);
+        self
+    }
+
+    /// Set the [`Hook`] that is notified when flushing or shutting down the
+    /// processor in the worker task fails.
+    ///
+    /// By default, these failures are reported to stderr.
+    ///
+    /// # Note
+    ///
+    /// This method is only available if called after [`worker_task`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tracing_forest::processor::hook::Silent;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// tracing_forest::worker_task()
+    ///     .hook(Silent)
+    ///     .build()
+    ///     .on(async {
+    ///         // ...
+    ///     })
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    /// [`Hook`]: hook::Hook
+    pub fn hook<H2: hook::Hook>(self, hook: H2) -> Builder<Tx, WorkerTask<P, H2>, T> {
+        Builder {
+            sender_processor: self.sender_processor,
+            worker_processor: WorkerTask {
+                processor: self.worker_processor.processor,
+                hook,
+            },
+            receiver: self.receiver,
+            tag: self.tag,
+            is_global: self.is_global,
+            flush_interval: self.flush_interval,
         }
     }
 }
@@ -332,8 +458,13 @@ where
     ///
     /// Since dropping the sender half would make the receiver task useless, this
     /// method uses traits to enforce at compile time that the function returns
-    /// some derivation of the sender. Currently, the only accepted wrapping is
-    /// through adding a fallback.
+    /// some derivation of the sender. Currently, the only accepted wrappings are
+    /// adding a fallback, teeing to another processor with [`Processor::and`],
+    /// and the [`filter`], [`map`], and [`filter_map`] combinators.
+    ///
+    /// [`filter`]: Processor::filter
+    /// [`map`]: Processor::map
+    /// [`filter_map`]: Processor::filter_map
     /// ```compile_fail
     /// use tracing_forest::PrettyPrinter;
     ///
@@ -362,6 +493,7 @@ where
             receiver: self.receiver,
             tag: self.tag,
             is_global: self.is_global,
+            flush_interval: self.flush_interval,
         }
     }
 
@@ -398,6 +530,7 @@ where
             receiver: self.receiver,
             tag,
             is_global: self.is_global,
+            flush_interval: self.flush_interval,
         }
     }
 
@@ -548,6 +681,7 @@ where
             worker_processor: self.worker_processor,
             receiver: self.receiver,
             is_global: self.is_global,
+            flush_interval: self.flush_interval,
         }
     }
 }
@@ -560,25 +694,44 @@ pub struct Runtime<S, P> {
     worker_processor: P, // either `Process<_>` or `Capture`
     receiver: UnboundedReceiver<Tree>,
     is_global: bool,
+    flush_interval: Option<Duration>,
 }
 
-impl<S, P> Runtime<S, WorkerTask<P>>
+impl<S, P, H> Runtime<S, WorkerTask<P, H>>
 where
     S: Subscriber + Send + Sync,
-    P: Processor + Send,
+    P: AsyncProcessor,
+    H: hook::Hook + Send + Sync,
 {
     /// Execute a future in the context of the configured subscriber.
     pub async fn on<F: Future>(self, f: F) -> F::Output {
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
-        let processor = self.worker_processor.0;
+        let WorkerTask { processor, hook } = self.worker_processor;
         let mut receiver = self.receiver;
+        let mut flush_interval = self.flush_interval.map(|period| {
+            let mut interval = time::interval_at(time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
 
         let handle = tokio::spawn(async move {
             loop {
+                let deadline = processor.next_flush_async();
+
+                // The shutdown sender is dropped without sending if `f` panics,
+                // in which case the worker still drains, flushes, and shuts down.
                 tokio::select! {
-                    Some(tree) = receiver.recv() => processor.process(tree).expect(fail::PROCESSING_ERROR),
-                    Ok(()) = &mut shutdown_rx => break,
-                    else => break,
+                    tree = receiver.recv() => match tree {
+                        Some(tree) => processor.process_async(tree).await.expect(fail::PROCESSING_ERROR),
+                        None => break,
+                    },
+                    () = tick(&mut flush_interval), if flush_interval.is_some() => {
+                        report_flush(&hook, processor.flush_async().await);
+                    }
+                    () = sleep_until(deadline), if deadline.is_some() => {
+                        report_flush(&hook, processor.flush_async().await);
+                    }
+                    _ = &mut shutdown_rx => break,
                 }
             }
 
@@ -586,7 +739,12 @@ where
 
             // Drain any remaining logs in the channel buffer.
             while let Ok(tree) = receiver.try_recv() {
-                processor.process(tree).expect(fail::PROCESSING_ERROR);
+                processor.process_async(tree).await.expect(fail::PROCESSING_ERROR);
+            }
+
+            report_flush(&hook, processor.flush_async().await);
+            if let Err(err) = processor.shutdown_async().await {
+                hook.on_shutdown_error(&*err);
             }
         });
 
@@ -614,6 +772,31 @@ where
     }
 }
 
+/// Reports a failed flush to the hook, since the worker task keeps running.
+fn report_flush(hook: &impl hook::Hook, result: processor::FlushResult) {
+    if let Err(err) = result {
+        hook.on_flush_error(&*err);
+    }
+}
+
+/// Completes at the next tick of the interval, or never if there isn't one.
+async fn tick(interval: &mut Option<Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => future::pending().await,
+    }
+}
+
+/// Completes once the deadline has passed, or never if there isn't one.
+async fn sleep_until(deadline: Option<std::time::Instant>) {
+    match deadline {
+        Some(deadline) => time::sleep_until(time::Instant::from_std(deadline)).await,
+        None => future::pending().await,
+    }
+}
+
 impl<S> Runtime<S, Capture>
 where
     S: Subscriber + Send + Sync,
@@ -0,0 +1,170 @@
+use crate::tag::Tag;
+#[cfg(feature = "timeline")]
+use crate::tree::Interval;
+use crate::tree::{Event, Field, FieldSet, Shared, Span, Tree};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use serde::de::{Deserialize, Deserializer, Error, MapAccess, Visitor};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tracing::Level;
+
+/// The most bytes [`intern`] will leak over the life of the process.
+///
+/// Deserialized tags are the only strings that are interned, and there are
+/// usually only a handful of distinct ones.
+const MAX_INTERNED_BYTES: usize = 1 << 20;
+
+/// Distinct strings leaked so far, and their total length in bytes.
+static INTERNED: Mutex<Option<(HashSet<&'static str>, usize)>> = Mutex::new(None);
+
+/// Returns a `&'static str` with the same contents as `s`.
+///
+/// The prefix and suffix of a [`Tag`] are `&'static str`s since they're
+/// normally written in the source. Deserialized ones are leaked instead, but
+/// only once per distinct string. Trees can come from untrusted sources such as
+/// a [`socket::Reader`], so once [`MAX_INTERNED_BYTES`] have been leaked, new
+/// strings are rejected instead.
+///
+/// [`socket::Reader`]: crate::processor::socket::Reader
+fn intern(s: &str) -> Result<&'static str, String> {
+    let mut interned = INTERNED.lock().unwrap_or_else(PoisonError::into_inner);
+    let (interned, bytes) = interned.get_or_insert_with(Default::default);
+
+    if let Some(s) = interned.get(s) {
+        return Ok(s);
+    }
+    if *bytes + s.len() > MAX_INTERNED_BYTES {
+        return Err(format!("too many distinct tags to intern {:?}", s));
+    }
+
+    let s: &'static str = Box::leak(s.into());
+    interned.insert(s);
+    *bytes += s.len();
+    Ok(s)
+}
+
+pub(super) fn level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Level, D::Error> {
+    let level = String::deserialize(deserializer)?;
+    level.parse().map_err(D::Error::custom)
+}
+
+pub(super) fn nanos<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let nanos = u128::deserialize(deserializer)?;
+    let secs = u64::try_from(nanos / 1_000_000_000).map_err(D::Error::custom)?;
+    Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+pub(super) fn fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FieldSet, D::Error> {
+    struct FieldsVisitor;
+
+    impl<'de> Visitor<'de> for FieldsVisitor {
+        type Value = FieldSet;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map of field keys to values")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<FieldSet, A::Error> {
+            let mut fields = FieldSet::default();
+            while let Some((key, value)) = map.next_entry::<String, String>()? {
+                fields.push(Field::new(key, value));
+            }
+            Ok(fields)
+        }
+    }
+
+    deserializer.deserialize_map(FieldsVisitor)
+}
+
+#[cfg(feature = "chrono")]
+pub(super) fn timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    let timestamp = String::deserialize(deserializer)?;
+    DateTime::parse_from_rfc3339(&timestamp)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(D::Error::custom)
+}
+
+/// The serialized form of an [`Event`], whose tag doesn't carry its icon.
+#[derive(serde::Deserialize)]
+pub(super) struct RawEvent {
+    #[serde(flatten)]
+    shared: Shared,
+    message: Option<String>,
+    tag: Option<String>,
+}
+
+impl TryFrom<RawEvent> for Event {
+    type Error = String;
+
+    fn try_from(raw: RawEvent) -> Result<Self, String> {
+        // Icons aren't serialized, so use the default icon for the level.
+        let level = raw.shared.level;
+        let tag = match raw.tag {
+            Some(tag) => {
+                let builder = Tag::builder().level(level);
+                let builder = match tag.rsplit_once('.') {
+                    Some((prefix, suffix)) => {
+                        builder.prefix(intern(prefix)?).suffix(intern(suffix)?)
+                    }
+                    None => builder.suffix(intern(&tag)?),
+                };
+                Some(builder.build())
+            }
+            None => None,
+        };
+
+        Ok(Event {
+            shared: raw.shared,
+            message: raw.message,
+            tag,
+        })
+    }
+}
+
+/// The serialized form of a [`Span`], whose name is owned.
+#[derive(serde::Deserialize)]
+pub(super) struct RawSpan {
+    #[serde(flatten)]
+    shared: Shared,
+    name: String,
+    #[serde(rename = "nanos_total", deserialize_with = "nanos")]
+    total_duration: Duration,
+    #[serde(rename = "nanos_nested", deserialize_with = "nanos")]
+    inner_duration: Duration,
+    nodes: Vec<Tree>,
+    #[cfg(feature = "timeline")]
+    #[serde(default)]
+    intervals: Vec<Interval>,
+    #[cfg(feature = "timeline")]
+    #[serde(default)]
+    enter_count: usize,
+    #[cfg(feature = "defer")]
+    #[serde(default)]
+    defer_unless_children_attached: bool,
+}
+
+impl TryFrom<RawSpan> for Span {
+    type Error = String;
+
+    fn try_from(raw: RawSpan) -> Result<Self, String> {
+        Ok(Span {
+            shared: raw.shared,
+            name: raw.name.into(),
+            total_duration: raw.total_duration,
+            inner_duration: raw.inner_duration,
+            nodes: raw.nodes,
+            #[cfg(feature = "timeline")]
+            intervals: raw.intervals,
+            #[cfg(feature = "timeline")]
+            enter_count: raw.enter_count,
+            #[cfg(feature = "defer")]
+            defer_unless_children_attached: raw.defer_unless_children_attached,
+        })
+    }
+}
@@ -2,19 +2,24 @@
 //!
 //! This module provides methods used for log inspection when using [`capture`].
 //! It consists of three types: [`Tree`], [`Span`], and [`Event`].
+//! When the `timeline` feature is enabled, spans also record each [`Interval`]
+//! during which they were entered.
 //!
 //! [`capture`]: crate::runtime::capture
 use crate::tag::Tag;
 #[cfg(feature = "chrono")]
 use chrono::{DateTime, Utc};
 #[cfg(feature = "serde")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::time::Duration;
 use thiserror::Error;
 use tracing::Level;
 #[cfg(feature = "uuid")]
 use uuid::Uuid;
 
+#[cfg(feature = "serde")]
+mod de;
 mod field;
 #[cfg(feature = "serde")]
 mod ser;
@@ -28,11 +33,18 @@ pub(crate) use field::FieldSet;
 /// the [`event`] and [`span`] methods provide a more ergonomic way to access the
 /// inner types in unit tests when combined with the [`capture`] function.
 ///
+/// With the `serde` feature, trees can be deserialized from the format they're
+/// serialized in. Tag icons aren't serialized, so deserialized tags use the
+/// default icon for their level. Since the parts of a [`Tag`] are
+/// `&'static str`s, each distinct tag that is deserialized is leaked once, up
+/// to 1 MiB over the life of the process, past which deserializing new ones
+/// fails.
+///
 /// [`event`]: Tree::event
 /// [`span`]: Tree::span
 /// [`capture`]: crate::runtime::capture
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[allow(clippy::large_enum_variant)] // https://github.com/rust-lang/rust-clippy/issues/9798
 pub enum Tree {
     /// An [`Event`] leaf node.
@@ -44,7 +56,8 @@ pub enum Tree {
 
 /// A leaf node in the log tree carrying information about a Tracing event.
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "de::RawEvent"))]
 pub struct Event {
     /// Shared fields between events and spans.
     #[cfg_attr(feature = "serde", serde(flatten))]
@@ -59,14 +72,15 @@ pub struct Event {
 
 /// An internal node in the log tree carrying information about a Tracing span.
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "de::RawSpan"))]
 pub struct Span {
     /// Shared fields between events and spans.
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub(crate) shared: Shared,
 
     /// The name of the span.
-    pub(crate) name: &'static str,
+    pub(crate) name: Cow<'static, str>,
 
     /// The total duration the span was open for.
     #[cfg_attr(
@@ -85,14 +99,53 @@ pub struct Span {
     /// Events and spans collected while the span was open.
     pub(crate) nodes: Vec<Tree>,
 
+    /// The intervals during which the span was entered.
+    #[cfg(feature = "timeline")]
+    pub(crate) intervals: Vec<Interval>,
+
+    /// The number of times the span was entered.
+    #[cfg(feature = "timeline")]
+    pub(crate) enter_count: usize,
+
     /// This span is only displayed *if* there are child nodes in the tree. Else it
     /// will NOT be rendered.
     #[cfg(feature = "defer")]
     pub(crate) defer_unless_children_attached: bool,
 }
 
+/// A period of time during which a [`Span`] was entered.
+///
+/// Interval starts are measured from when the root span of the tree was opened,
+/// so the intervals of all spans within a single tree share the same origin.
+#[cfg(feature = "timeline")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Interval {
+    /// The time between the root span opening and the span being entered.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "nanos_start",
+            serialize_with = "ser::nanos",
+            deserialize_with = "de::nanos"
+        )
+    )]
+    pub(crate) start: Duration,
+
+    /// The time between the span being entered and exited.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "nanos_duration",
+            serialize_with = "ser::nanos",
+            deserialize_with = "de::nanos"
+        )
+    )]
+    pub(crate) duration: Duration,
+}
+
 #[derive(Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub(crate) struct Shared {
     /// The ID of the event or span.
     #[cfg(feature = "uuid")]
@@ -100,15 +153,24 @@ pub(crate) struct Shared {
 
     /// When the event occurred or when the span opened.
     #[cfg(feature = "chrono")]
-    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::timestamp"))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "ser::timestamp", deserialize_with = "de::timestamp")
+    )]
     pub(crate) timestamp: DateTime<Utc>,
 
     /// The level the event or span occurred at.
-    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::level"))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "ser::level", deserialize_with = "de::level")
+    )]
     pub(crate) level: Level,
 
     /// Key-value data.
-    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::fields"))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "ser::fields", deserialize_with = "de::fields")
+    )]
     pub(crate) fields: FieldSet,
 }
 
@@ -215,6 +277,23 @@ impl Tree {
 }
 
 impl Event {
+    /// Creates an event generated by this crate rather than recorded from
+    /// Tracing, such as one summarizing other trees.
+    pub(crate) fn synthetic(level: Level, message: String, fields: FieldSet) -> Self {
+        Event {
+            shared: Shared {
+                #[cfg(feature = "uuid")]
+                uuid: Uuid::nil(),
+                #[cfg(feature = "chrono")]
+                timestamp: Utc::now(),
+                level,
+                fields,
+            },
+            message: Some(message),
+            tag: None,
+        }
+    }
+
     /// Returns the event's [`Uuid`].
     #[cfg(feature = "uuid")]
     pub fn uuid(&self) -> Uuid {
@@ -252,10 +331,14 @@ impl Span {
     pub(crate) fn new(shared: Shared, name: &'static str) -> Self {
         Span {
             shared,
-            name,
+            name: Cow::Borrowed(name),
             total_duration: Duration::ZERO,
             inner_duration: Duration::ZERO,
             nodes: Vec::new(),
+            #[cfg(feature = "timeline")]
+            intervals: Vec::new(),
+            #[cfg(feature = "timeline")]
+            enter_count: 0,
             #[cfg(feature = "defer")]
             defer_unless_children_attached: false,
         }
@@ -296,7 +379,7 @@ impl Span {
 
     /// Returns the span's name.
     pub fn name(&self) -> &str {
-        self.name
+        &self.name
     }
 
     /// Returns the span's fields.
@@ -327,4 +410,73 @@ impl Span {
     pub fn base_duration(&self) -> Duration {
         self.total_duration - self.inner_duration
     }
+
+    /// Returns every interval during which the span was entered, in the order
+    /// they occurred.
+    ///
+    /// If the span was used to instrument a `Future`, each interval corresponds
+    /// to a single poll.
+    ///
+    /// # Examples
+    ///
+    /// Inspecting how a polled span's execution was spread out:
+    /// ```
+    /// use tracing::{info_span, Instrument};
+    /// use tokio::task::yield_now;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let logs = tracing_forest::capture()
+    ///         .build()
+    ///         .on(async {
+    ///             async {
+    ///                 yield_now().await;
+    ///             }
+    ///             .instrument(info_span!("my_span"))
+    ///             .await;
+    ///         })
+    ///         .await;
+    ///
+    ///     let my_span = logs[0].span()?;
+    ///     assert!(my_span.enter_count() >= 2);
+    ///
+    ///     for interval in my_span.intervals() {
+    ///         println!("entered at {:?} for {:?}", interval.start(), interval.duration());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "timeline")]
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    /// Returns the number of times the span was entered.
+    ///
+    /// If the span was used to instrument a `Future`, this is the number of
+    /// times the `Future` was polled.
+    #[cfg(feature = "timeline")]
+    pub fn enter_count(&self) -> usize {
+        self.enter_count
+    }
+}
+
+#[cfg(feature = "timeline")]
+impl Interval {
+    /// Returns the time between the root span of the tree opening and the
+    /// start of this interval.
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    /// Returns the length of the interval.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the time between the root span of the tree opening and the
+    /// end of this interval.
+    pub fn end(&self) -> Duration {
+        self.start + self.duration
+    }
 }
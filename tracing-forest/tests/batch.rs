@@ -0,0 +1,264 @@
+use std::sync::{Arc, Mutex};
+use tracing_forest::processor::batch::{self, Batch};
+use tracing_forest::processor::Processor;
+use tracing_forest::tree::Tree;
+use tracing_forest::{util::*, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+type Batches = Arc<Mutex<Vec<Vec<String>>>>;
+
+fn collect(batches: &Batches) -> impl Fn(Vec<Tree>) -> batch::Result {
+    let batches = batches.clone();
+    move |trees| {
+        let messages = trees
+            .iter()
+            .map(|tree| tree.event().unwrap().message().unwrap().to_string())
+            .collect();
+        batches.lock().unwrap().push(messages);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_batches_by_count_and_flushes_on_drop() {
+    let batches = Batches::default();
+    let processor = Batch::new(collect(&batches)).max_trees(3);
+    let subscriber = Registry::default().with(ForestLayer::from(processor));
+
+    tracing::subscriber::with_default(subscriber, || {
+        for i in 0..7 {
+            info!("{}", i);
+        }
+    });
+
+    assert_eq!(
+        *batches.lock().unwrap(),
+        [vec!["0", "1", "2"], vec!["3", "4", "5"], vec!["6"]]
+    );
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn test_failed_batch_goes_to_fallback_and_is_not_retried_on_drop() {
+    let attempts = Arc::new(Mutex::new(0));
+    let batches = Batches::default();
+    let fallback = Arc::new(Mutex::new(Vec::new()));
+
+    let inner = {
+        let attempts = attempts.clone();
+        let collect = collect(&batches);
+        move |trees: Vec<Tree>| {
+            let mut attempts = attempts.lock().unwrap();
+            *attempts += 1;
+            if *attempts == 1 {
+                Err(batch::error(trees, "sink is down".into()))
+            } else {
+                collect(trees)
+            }
+        }
+    };
+
+    let processor = Batch::new(inner).max_trees(2).or({
+        let fallback = fallback.clone();
+        tracing_forest::processor::from_fn(move |tree| {
+            let message = tree.event().unwrap().message().unwrap().to_string();
+            fallback.lock().unwrap().push(message);
+            Ok(())
+        })
+    });
+    let subscriber = Registry::default().with(ForestLayer::from(processor));
+
+    tracing::subscriber::with_default(subscriber, || {
+        for i in 0..3 {
+            info!("{}", i);
+        }
+    });
+
+    // The tree completing the failed batch goes to the fallback, and the rest
+    // are kept until the `Batch` is flushed. Dropping the `Batch` processes the
+    // buffered trees, but doesn't retry the failed ones.
+    assert_eq!(*fallback.lock().unwrap(), ["1"]);
+    assert_eq!(*batches.lock().unwrap(), [vec!["2"]]);
+}
+
+#[test]
+fn test_flush_returns_failed_trees() {
+    let processor =
+        Arc::new(Batch::new(|trees| Err(batch::error(trees, "sink is down".into()))).max_trees(2));
+    let subscriber = Registry::default().with(ForestLayer::from(processor.clone().or_none()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        for i in 0..3 {
+            info!("{}", i);
+        }
+    });
+
+    let err = processor.flush().unwrap_err();
+    let err = err.downcast::<batch::Error>().unwrap();
+    let messages: Vec<_> = err
+        .trees
+        .iter()
+        .map(|tree| tree.event().unwrap().message().unwrap())
+        .collect();
+
+    assert_eq!(messages, ["0", "2"]);
+    assert!(processor.flush().is_ok());
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn test_flush_returns_trees_the_fallback_rejects() {
+    let fallback = Arc::new(Mutex::new(Vec::new()));
+    let processor = Arc::new(
+        Batch::new(|trees| Err(batch::error(trees, "sink is down".into()))).or({
+            let fallback = fallback.clone();
+            tracing_forest::processor::from_fn(move |tree| {
+                let message = tree.event().unwrap().message().unwrap().to_string();
+                if message == "1" {
+                    fallback.lock().unwrap().push(message);
+                    Ok(())
+                } else {
+                    Err(tracing_forest::processor::error(
+                        tree,
+                        "fallback is down".into(),
+                    ))
+                }
+            })
+        }),
+    );
+    let subscriber = Registry::default().with(ForestLayer::from(processor.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        for i in 0..3 {
+            info!("{}", i);
+        }
+    });
+
+    // Every tree is handed to the fallback, even after it fails.
+    let err = processor.flush().unwrap_err();
+    let err = err.downcast::<batch::Error>().unwrap();
+    let messages: Vec<_> = err
+        .trees
+        .iter()
+        .map(|tree| tree.event().unwrap().message().unwrap())
+        .collect();
+
+    assert_eq!(messages, ["0", "2"]);
+    assert_eq!(err.to_string(), "fallback is down");
+    assert_eq!(*fallback.lock().unwrap(), ["1"]);
+}
+
+#[test]
+fn test_flush_returns_errors_without_trees() {
+    let processor = Arc::new(
+        Batch::new(|_trees| Err(batch::error(Vec::new(), "sink is down".into()))).max_trees(1),
+    );
+    let subscriber = Registry::default().with(ForestLayer::from(processor.clone()));
+
+    tracing::subscriber::with_default(subscriber, || {
+        info!("lost");
+    });
+
+    let err = processor.flush().unwrap_err();
+    assert_eq!(err.to_string(), "sink is down");
+}
+
+#[test]
+fn test_flush_guard_flushes_a_layer_that_outlives_it() {
+    let batches = Batches::default();
+    let (processor, guard) = Batch::new(collect(&batches)).max_trees(100).flush_guard();
+    let subscriber = Registry::default().with(ForestLayer::from(processor));
+
+    // The subscriber is kept alive, as it would be if it was installed with
+    // `init`, so only the guard can flush the batch.
+    let _default = tracing::subscriber::set_default(subscriber);
+    info!("buffered");
+    assert!(batches.lock().unwrap().is_empty());
+
+    drop(guard);
+    assert_eq!(*batches.lock().unwrap(), [vec!["buffered"]]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_worker_task_flushes_idle_batches() {
+    use std::time::Duration;
+
+    let batches = Batches::default();
+    let processor = Batch::new(collect(&batches)).max_trees(100);
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| processor)
+        .flush_interval(Duration::from_millis(10))
+        .build()
+        .on(async {
+            info!("first");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert_eq!(*batches.lock().unwrap(), [vec!["first"]]);
+            info!("second");
+        })
+        .await;
+
+    assert_eq!(*batches.lock().unwrap(), [vec!["first"], vec!["second"]]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_worker_task_flushes_batches_after_max_delay() {
+    use std::time::Duration;
+
+    let batches = Batches::default();
+    let processor = Batch::new(collect(&batches))
+        .max_trees(100)
+        .max_delay(Duration::from_millis(10));
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| processor)
+        .build()
+        .on(async {
+            info!("first");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert_eq!(*batches.lock().unwrap(), [vec!["first"]]);
+            info!("second");
+        })
+        .await;
+
+    assert_eq!(*batches.lock().unwrap(), [vec!["first"], vec!["second"]]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+#[allow(clippy::result_large_err)]
+async fn test_worker_task_gives_failed_batches_to_fallback() {
+    use std::time::Duration;
+
+    let fallback = Arc::new(Mutex::new(Vec::new()));
+    let processor = Batch::new(|trees| Err(batch::error(trees, "sink is down".into())))
+        .max_trees(10)
+        .or({
+            let fallback = fallback.clone();
+            tracing_forest::processor::from_fn(move |tree| {
+                let message = tree.event().unwrap().message().unwrap().to_string();
+                fallback.lock().unwrap().push(message);
+                Ok(())
+            })
+        })
+        .hook(tracing_forest::processor::hook::Silent);
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| processor)
+        .flush_interval(Duration::from_millis(10))
+        .build()
+        .on(async {
+            info!("first");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert_eq!(*fallback.lock().unwrap(), ["first"]);
+            info!("second");
+        })
+        .await;
+
+    assert_eq!(*fallback.lock().unwrap(), ["first", "second"]);
+}
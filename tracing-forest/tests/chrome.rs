@@ -0,0 +1,77 @@
+#![cfg(all(feature = "tokio", feature = "chrono", feature = "json"))]
+mod common;
+
+use std::fs;
+use tracing_forest::printer::ChromeTrace;
+use tracing_forest::processor::file::RollingFile;
+use tracing_forest::{util::*, ForestLayer, Formatter};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+fn parse(output: &str) -> Vec<serde_json::Value> {
+    output
+        .lines()
+        .filter(|line| *line != "[")
+        .map(|line| serde_json::from_str(line.trim_end_matches(',')).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_chrome_trace_events() -> Result<(), Box<dyn std::error::Error>> {
+    let logs = common::capture_trees(|| {
+        info_span!("first", answer = 42).in_scope(|| {
+            info!(ok = true, "hello");
+        });
+        info_span!("second").in_scope(|| {});
+    })
+    .await;
+
+    let chrome = ChromeTrace::new();
+    let first = chrome.fmt(&logs[0])?;
+    let second = chrome.fmt(&logs[1])?;
+
+    assert_eq!(chrome.header(), "[\n");
+    assert!(!first.starts_with('['));
+    assert_eq!(chrome.fmt(&logs[0])?, first);
+
+    let first = parse(&first);
+    assert_eq!(first.len(), 2);
+    assert_eq!(first[0]["ph"], "X");
+    assert_eq!(first[0]["name"], "first");
+    assert_eq!(first[0]["args"]["answer"], "42");
+    assert!(first[0]["dur"].is_number());
+    assert_eq!(first[1]["ph"], "i");
+    assert_eq!(first[1]["name"], "hello");
+    assert_eq!(first[1]["args"]["ok"], "true");
+    assert!(first[1]["ts"].as_f64() >= first[0]["ts"].as_f64());
+
+    let second = parse(&second);
+    assert_eq!(second[0]["name"], "second");
+    assert_ne!(second[0]["tid"], first[0]["tid"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_chrome_trace_opens_every_rotated_file() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("tracing-forest-chrome-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+
+    let processor = RollingFile::new(&dir, "trace.json")
+        .formatter(ChromeTrace::new())
+        .max_size(1);
+    let subscriber = Registry::default().with(ForestLayer::from(processor));
+    tracing::subscriber::with_default(subscriber, || {
+        info!("first");
+        info!("second");
+    });
+
+    for name in ["trace.json", "trace.json.1"] {
+        let contents = fs::read_to_string(dir.join(name))?;
+        assert!(contents.starts_with("[\n"));
+        assert_eq!(parse(&contents).len(), 1);
+    }
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
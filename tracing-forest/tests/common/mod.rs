@@ -0,0 +1,92 @@
+//! Fixtures shared by the integration tests.
+//!
+//! Each test file only uses some of them.
+#![allow(dead_code)]
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing_forest::processor::{self, Processor};
+use tracing_forest::tree::Tree;
+#[cfg(feature = "tokio")]
+use tracing_forest::util::*;
+use tracing_forest::ForestLayer;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+/// Runs `f` with a subscriber that sends trees to `processor`.
+pub fn with_processor(processor: impl Processor + Send + Sync, f: impl FnOnce()) {
+    let subscriber = Registry::default().with(ForestLayer::from(processor));
+    tracing::subscriber::with_default(subscriber, f);
+}
+
+/// Returns the trees generated by `f`.
+#[cfg(feature = "tokio")]
+pub async fn capture_trees(f: impl FnOnce()) -> Vec<Tree> {
+    tracing_forest::capture().build().on(async { f() }).await
+}
+
+/// Returns the first tree generated by `f`.
+#[cfg(feature = "tokio")]
+pub async fn capture_tree(f: impl FnOnce()) -> Tree {
+    capture_trees(f).await.remove(0)
+}
+
+/// Returns a span with a field, containing an event with fields.
+#[cfg(feature = "tokio")]
+pub async fn hello_tree() -> Tree {
+    capture_tree(|| {
+        info_span!("my_span", answer = 42).in_scope(|| {
+            info!(user = "admin", ok = true, "hello");
+        });
+    })
+    .await
+}
+
+/// Returns a span containing an event and a nested span, which both have
+/// fields.
+#[cfg(feature = "tokio")]
+pub async fn login_tree() -> Tree {
+    capture_tree(|| {
+        info_span!("server", path = "/login").in_scope(|| {
+            info!("received");
+            info_span!("auth", user = "admin").in_scope(|| {
+                warn!(path = "/admin", "bad password");
+            });
+        });
+    })
+    .await
+}
+
+/// Returns the message of a root event, or the name of a root span.
+pub fn message(tree: &Tree) -> String {
+    match tree {
+        Tree::Event(event) => event.message().unwrap_or_default().to_string(),
+        Tree::Span(span) => span.name().to_string(),
+    }
+}
+
+/// A formatter that writes the [`message`] of each tree on its own line.
+pub fn message_line(tree: &Tree) -> Result<String, fmt::Error> {
+    Ok(message(tree) + "\n")
+}
+
+/// A processor that stores every tree it receives.
+#[derive(Clone, Default)]
+pub struct Collect(Arc<Mutex<Vec<Tree>>>);
+
+impl Collect {
+    /// Returns the stored trees.
+    pub fn trees(&self) -> MutexGuard<'_, Vec<Tree>> {
+        self.0.lock().unwrap()
+    }
+
+    /// Returns the [`message`] of each stored tree.
+    pub fn messages(&self) -> Vec<String> {
+        self.trees().iter().map(message).collect()
+    }
+}
+
+impl Processor for Collect {
+    fn process(&self, tree: Tree) -> processor::Result {
+        self.trees().push(tree);
+        Ok(())
+    }
+}
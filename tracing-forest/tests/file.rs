@@ -0,0 +1,111 @@
+mod common;
+
+use common::{message_line, with_processor};
+use std::fs;
+use std::path::PathBuf;
+use tracing_forest::processor::file::{RollingFile, Rotation};
+use tracing_forest::util::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tracing-forest-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn file_names(dir: &PathBuf) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_rotates_by_size_and_keeps_max_files() {
+    let dir = temp_dir("size");
+    let processor = RollingFile::new(&dir, "app.log")
+        .formatter(message_line)
+        .max_size(13)
+        .max_files(2);
+
+    with_processor(processor, || {
+        info!("first");
+        info!("second");
+        info!("third");
+        info!("fourth");
+        info!("fifth");
+    });
+
+    let read = |name| fs::read_to_string(dir.join(name)).unwrap();
+
+    assert_eq!(file_names(&dir), ["app.log.1", "app.log.2"]);
+    assert_eq!(read("app.log.1"), "third\nfourth\n");
+    assert_eq!(read("app.log.2"), "fifth\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_daily_rotation_resumes_existing_file() {
+    let dir = temp_dir("daily");
+
+    for message in ["first", "second"] {
+        let processor = RollingFile::new(&dir, "app")
+            .formatter(message_line)
+            .rotation(Rotation::Daily);
+
+        with_processor(processor, || info!("{}", message));
+    }
+
+    let names = file_names(&dir);
+    assert_eq!(names.len(), 1);
+
+    // app.YYYY-MM-DD
+    let date = names[0].strip_prefix("app.").unwrap();
+    assert_eq!(date.len(), 10);
+    assert_eq!(&date[4..5], "-");
+    assert_eq!(&date[7..8], "-");
+
+    assert_eq!(
+        fs::read_to_string(dir.join(&names[0])).unwrap(),
+        "first\nsecond\n"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_max_files_only_deletes_rotated_files() {
+    let dir = temp_dir("prune").join("logs");
+    let processor = RollingFile::new(&dir, "app")
+        .formatter(message_line)
+        .max_size(1)
+        .max_files(1);
+
+    // The directory doesn't exist yet, so it's created by the first write.
+    with_processor(processor, || info!("first"));
+
+    for name in ["app.log", "app.log.1", "app.1.bak", "app.2022-03-24.txt"] {
+        fs::write(dir.join(name), "").unwrap();
+    }
+    let processor = RollingFile::new(&dir, "app")
+        .formatter(message_line)
+        .max_size(1)
+        .max_files(1);
+    with_processor(processor, || info!("second"));
+
+    assert_eq!(
+        file_names(&dir),
+        [
+            "app.1",
+            "app.1.bak",
+            "app.2022-03-24.txt",
+            "app.log",
+            "app.log.1"
+        ]
+    );
+
+    fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "tokio")]
+mod common;
+
+use tracing_forest::printer::Flat;
+use tracing_forest::{util::*, Formatter};
+
+#[tokio::test]
+async fn test_logfmt_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::login_tree().await;
+
+    let output = Flat::logfmt().fmt(&tree)?;
+    let lines: Vec<_> = output.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("level=INFO"));
+    assert!(lines[0].ends_with(r#"span=server msg=received path="\"/login\"""#));
+    assert!(lines[1].contains("level=WARN"));
+    assert!(lines[1].ends_with(
+        r#"span="server > auth" msg="bad password" path="\"/admin\"" user="\"admin\"""#
+    ));
+
+    let output = Flat::logfmt().span_closes(true).fmt(&tree)?;
+    let lines: Vec<_> = output.lines().collect();
+
+    assert_eq!(lines.len(), 4);
+    assert!(lines[2].contains(r#"span="server > auth" msg=close nanos_total="#));
+    assert!(lines[3].contains("span=server msg=close nanos_total="));
+
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn test_json_lines() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::login_tree().await;
+
+    let output = Flat::json().span_closes(true).fmt(&tree)?;
+    let lines = output
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[1]["span"], "server > auth");
+    assert_eq!(lines[1]["msg"], "bad password");
+    assert_eq!(lines[1]["path"], "\"/admin\"");
+    assert_eq!(lines[1]["user"], "\"admin\"");
+    assert!(lines[3]["nanos_total"].is_u64());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fields_dont_overwrite_line_values() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::capture_tree(|| {
+        info_span!("server", span = "fake").in_scope(|| {
+            info!(level = "DEBUG", msg = "forged", tag = 1, "real");
+        });
+    })
+    .await;
+
+    let output = Flat::logfmt().fmt(&tree)?;
+
+    assert!(output.contains("level=INFO"));
+    assert!(output.contains(
+        r#"span=server msg=real field.span="\"fake\"" field.level="\"DEBUG\"" field.msg="\"forged\"" field.tag=1"#
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_logfmt_values_cant_add_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::capture_tree(|| {
+        info!(user = %r#""guest" admin="true""#, "login");
+    })
+    .await;
+
+    let output = Flat::logfmt().fmt(&tree)?;
+    let line = output.trim_end();
+
+    assert!(line.ends_with(r#"msg=login user="\"guest\" admin=\"true\"""#));
+
+    Ok(())
+}
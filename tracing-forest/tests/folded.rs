@@ -0,0 +1,100 @@
+#![cfg(feature = "tokio")]
+mod common;
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use tracing_forest::printer::Folded;
+use tracing_forest::processor::folded::FoldedStacks;
+use tracing_forest::tree::Tree;
+use tracing_forest::{util::*, Formatter, Processor};
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns two trees, each with a span entered twice in a row.
+async fn nested_trees() -> Vec<Tree> {
+    common::capture_trees(|| {
+        for _ in 0..2 {
+            info_span!("outer").in_scope(|| {
+                info_span!("inner").in_scope(|| {
+                    info!("ignored");
+                });
+                info_span!("inner").in_scope(|| {});
+            });
+        }
+    })
+    .await
+}
+
+/// Parses folded lines into their stacks and weights.
+fn parse(output: &str) -> Vec<(String, u128)> {
+    output
+        .lines()
+        .map(|line| {
+            let (stack, weight) = line.rsplit_once(' ').unwrap();
+            (stack.to_string(), weight.parse().unwrap())
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_folded_formatter() -> Result<(), Box<dyn std::error::Error>> {
+    let trees = nested_trees().await;
+
+    let lines = parse(&Folded.fmt(&trees[0])?);
+    let stacks: Vec<_> = lines.iter().map(|(stack, _)| stack.as_str()).collect();
+    assert_eq!(stacks, ["outer", "outer;inner"]);
+
+    // Both `inner` spans are combined.
+    let span = trees[0].span()?;
+    let inner: u128 = span
+        .nodes()
+        .iter()
+        .map(|node| node.span().unwrap().base_duration().as_nanos())
+        .sum();
+    assert_eq!(lines[1].1, inner);
+    assert_eq!(lines[0].1, span.base_duration().as_nanos());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_folded_stacks_aggregate_across_trees(
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let trees = nested_trees().await;
+    let expected: u128 = trees
+        .iter()
+        .map(|tree| tree.span().unwrap().base_duration().as_nanos())
+        .sum();
+
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let processor = FoldedStacks::new().writer(move || writer.clone());
+
+    for tree in trees {
+        processor.process(tree)?;
+    }
+    assert!(buffer.0.lock().unwrap().is_empty());
+
+    processor.flush()?;
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone())?;
+    let lines = parse(&output);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], ("outer".to_string(), expected));
+
+    // The stacks are cleared once written.
+    processor.shutdown()?;
+    assert_eq!(buffer.0.lock().unwrap().len(), output.len());
+
+    Ok(())
+}
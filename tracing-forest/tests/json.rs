@@ -0,0 +1,58 @@
+#![cfg(all(feature = "tokio", feature = "json"))]
+mod common;
+
+use tracing_forest::printer::Json;
+use tracing_forest::tree::Tree;
+use tracing_forest::{util::*, Formatter};
+
+#[tokio::test]
+async fn test_json_formats() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::hello_tree().await;
+
+    let line = Json::new().fmt(&tree)?;
+    assert!(line.ends_with('\n'));
+    assert_eq!(line.lines().count(), 1);
+
+    let parsed: Tree = serde_json::from_str(&line)?;
+    let span = parsed.span()?;
+    assert_eq!(span.name(), "my_span");
+    assert_eq!(span.nodes()[0].event()?.message(), Some("hello"));
+
+    let pretty = Json::new().pretty(true).newline(false).fmt(&tree)?;
+    assert!(!pretty.ends_with('\n'));
+    assert!(pretty.lines().count() > 1);
+    assert_eq!(
+        serde_json::from_str::<Tree>(&pretty)?.span()?.name(),
+        "my_span"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_json_only_limits_distinct_tags() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::capture_tree(|| {
+        info_span!("my_span", my_key = 1).in_scope(|| {
+            info!("hello");
+        });
+    })
+    .await;
+    let line = Json::new().fmt(&tree)?;
+
+    // Span names and field keys are owned, so they aren't limited.
+    let name = "x".repeat(2 << 20);
+    let key = "y".repeat(2 << 20);
+    let parsed: Tree =
+        serde_json::from_str(&line.replace("my_span", &name).replace("my_key", &key))?;
+    let span = parsed.span()?;
+    assert_eq!(span.name(), name);
+    assert_eq!(span.fields()[0].key(), key);
+
+    // Tags are leaked, so a huge one is rejected.
+    let huge = line.replace("\"tag\":null", &format!("\"tag\":\"{name}\""));
+    assert_ne!(huge, line);
+    assert!(serde_json::from_str::<Tree>(&huge).is_err());
+    assert!(serde_json::from_str::<Tree>(&line).is_ok());
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+#![cfg(all(
+    feature = "tokio",
+    feature = "uuid",
+    feature = "chrono",
+    feature = "json"
+))]
+mod common;
+
+use serde_json::Value;
+use tracing_forest::printer::Otlp;
+use tracing_forest::{util::*, Formatter};
+
+/// Returns the value of the attribute `key` from a list of OTLP attributes.
+fn attribute<'a>(attributes: &'a Value, key: &str) -> &'a Value {
+    let attribute = attributes
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|attribute| attribute["key"] == key)
+        .unwrap();
+    &attribute["value"]
+}
+
+#[tokio::test]
+async fn test_otlp_resource_spans() -> Result<(), Box<dyn std::error::Error>> {
+    let logs = common::capture_trees(|| {
+        info_span!("outer", answer = 42).in_scope(|| {
+            info_span!("inner", user = "admin").in_scope(|| {
+                warn!(ok = true, "careful");
+            });
+            info_span!("own", uuid = %"00000000-0000-0000-0123-456789abcdef").in_scope(|| {
+                info_span!("own_child").in_scope(|| {});
+            });
+        });
+        info!(answer = 42, "alone");
+    })
+    .await;
+
+    let otlp = Otlp::new().service_name(String::from("my-app"));
+
+    // Events outside of any span are written as log records.
+    let request: Value = serde_json::from_str(&otlp.fmt(&logs[1])?)?;
+    let resource_logs = &request["resourceLogs"][0];
+    assert_eq!(
+        attribute(&resource_logs["resource"]["attributes"], "service.name")["stringValue"],
+        "my-app"
+    );
+    let record = &resource_logs["scopeLogs"][0]["logRecords"][0];
+    assert_eq!(record["body"]["stringValue"], "alone");
+    assert_eq!(record["severityText"], "INFO");
+    assert_eq!(record["severityNumber"], 9);
+    assert_eq!(attribute(&record["attributes"], "answer")["intValue"], "42");
+
+    let output = otlp.fmt(&logs[0])?;
+    assert!(output.ends_with('\n'));
+    assert_eq!(output.lines().count(), 1);
+
+    let request: Value = serde_json::from_str(&output)?;
+    let resource_spans = &request["resourceSpans"][0];
+    assert_eq!(
+        attribute(&resource_spans["resource"]["attributes"], "service.name")["stringValue"],
+        "my-app"
+    );
+
+    let scope_spans = &resource_spans["scopeSpans"][0];
+    assert_eq!(scope_spans["scope"]["name"], "tracing-forest");
+
+    let spans = scope_spans["spans"].as_array().unwrap();
+    assert_eq!(spans.len(), 4);
+    let (outer, inner, own, own_child) = (&spans[0], &spans[1], &spans[2], &spans[3]);
+
+    let uuid = logs[0].span()?.uuid();
+    assert_eq!(outer["traceId"], uuid.simple().to_string());
+    assert_eq!(inner["traceId"], outer["traceId"]);
+    assert_eq!(outer["spanId"].as_str().unwrap().len(), 16);
+    assert_ne!(inner["spanId"], outer["spanId"]);
+    assert_eq!(inner["parentSpanId"], outer["spanId"]);
+    assert!(outer.get("parentSpanId").is_none());
+
+    // A span with its own uuid gets its span id from it.
+    assert_eq!(own["traceId"], outer["traceId"]);
+    assert_eq!(own["spanId"], "0123456789abcdef");
+    assert_eq!(own["parentSpanId"], outer["spanId"]);
+    assert_ne!(own_child["spanId"], own["spanId"]);
+    assert_eq!(own_child["parentSpanId"], own["spanId"]);
+
+    assert_eq!(outer["name"], "outer");
+    assert_eq!(attribute(&outer["attributes"], "answer")["intValue"], "42");
+    assert_eq!(
+        attribute(&inner["attributes"], "user")["stringValue"],
+        "admin"
+    );
+
+    let start: u64 = outer["startTimeUnixNano"].as_str().unwrap().parse()?;
+    let end: u64 = outer["endTimeUnixNano"].as_str().unwrap().parse()?;
+    assert!(start <= end);
+
+    let event = &inner["events"][0];
+    assert_eq!(event["name"], "careful");
+    assert_eq!(attribute(&event["attributes"], "ok")["boolValue"], true);
+    assert_eq!(
+        attribute(&event["attributes"], "level")["stringValue"],
+        "WARN"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::Collect;
+use std::thread;
+use tracing_forest::processor::ring::RingBuffer;
+use tracing_forest::{util::*, ForestLayer};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+#[test]
+fn test_panic_hook_reports_history_and_open_spans() -> Result<(), Box<dyn std::error::Error>> {
+    let ring = RingBuffer::new(8);
+    let collect = Collect::default();
+
+    tracing_forest::panic::hook()
+        .ring(ring.handle())
+        .processor(collect.clone())
+        .install();
+
+    let result = thread::spawn(move || {
+        let subscriber = Registry::default().with(ForestLayer::from(ring));
+        tracing::subscriber::with_default(subscriber, || {
+            info!("history");
+            info_span!("outer").in_scope(|| {
+                info!("before panic");
+
+                // Another subscriber opens and closes a span with the same ID
+                // as "outer", which must not affect what the hook reports.
+                let other = Registry::default().with(ForestLayer::sink());
+                tracing::subscriber::with_default(other, || {
+                    info_span!("other").in_scope(|| {});
+                });
+
+                info_span!("inner").in_scope(|| panic!("boom"));
+            });
+        });
+    })
+    .join();
+
+    assert!(result.is_err());
+
+    let trees = collect.trees();
+    assert_eq!(trees.len(), 3);
+
+    assert_eq!(trees[0].event()?.message(), Some("history"));
+
+    let outer = trees[1].span()?;
+    assert_eq!(outer.name(), "outer");
+    assert_eq!(outer.nodes()[0].event()?.message(), Some("before panic"));
+    assert_eq!(outer.nodes()[1].span()?.name(), "inner");
+
+    let panicked = trees[2].event()?;
+    assert_eq!(panicked.level(), Level::ERROR);
+    assert!(panicked.message().unwrap().ends_with("boom"));
+    assert_eq!(panicked.fields()[0].key(), "span");
+    assert_eq!(panicked.fields()[0].value(), "outer > inner");
+    drop(trees);
+
+    // The hook is process-wide, so a panicking `Debug` in a recorded field is
+    // checked here rather than in a separate test.
+    struct Boom;
+
+    impl std::fmt::Debug for Boom {
+        fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            panic!("bad debug")
+        }
+    }
+
+    collect.trees().clear();
+
+    let result = thread::spawn(|| {
+        let subscriber = Registry::default().with(ForestLayer::sink());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = info_span!("recorded", value = tracing::field::Empty);
+            span.in_scope(|| {
+                span.record("value", tracing::field::debug(Boom));
+            });
+        });
+    })
+    .join();
+
+    assert!(result.is_err());
+
+    let trees = collect.trees();
+    let recorded = trees[trees.len() - 2].span()?;
+    assert_eq!(recorded.name(), "recorded");
+
+    let panicked = trees[trees.len() - 1].event()?;
+    assert!(panicked.message().unwrap().ends_with("bad debug"));
+    assert_eq!(panicked.fields()[0].value(), "recorded");
+    drop(trees);
+
+    collect.trees().clear();
+
+    // A panic while the span's extensions are locked must not deadlock the
+    // hook. The lock is poisoned afterwards, so the span is never closed.
+    let result = thread::spawn(|| {
+        let subscriber = Registry::default().with(ForestLayer::sink());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = std::mem::ManuallyDrop::new(info_span!("locked"));
+            info!(parent: &*span, "hello");
+
+            let dispatch = tracing::dispatcher::get_default(|dispatch| dispatch.clone());
+            let registry = dispatch.downcast_ref::<Registry>().unwrap();
+            let span_ref = registry.span(&span.id().unwrap()).unwrap();
+            let _extensions = span_ref.extensions_mut();
+            panic!("locked");
+        });
+    })
+    .join();
+
+    assert!(result.is_err());
+
+    let trees = collect.trees();
+    assert!(trees.len() >= 2);
+    let locked = trees[trees.len() - 2].span()?;
+    assert_eq!(locked.name(), "locked");
+    assert_eq!(locked.nodes()[0].event()?.message(), Some("hello"));
+
+    let panicked = trees[trees.len() - 1].event()?;
+    assert!(panicked.message().unwrap().ends_with("locked"));
+
+    Ok(())
+}
@@ -0,0 +1,379 @@
+#![cfg(feature = "tokio")]
+mod common;
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing_forest::printer::pretty::{Glyphs, IconMode};
+use tracing_forest::printer::{ColorMode, MakeTerminal, Pretty};
+use tracing_forest::{util::*, Formatter, Printer, Processor};
+use tracing_subscriber::fmt::MakeWriter;
+
+#[tokio::test]
+async fn test_compact_output() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::hello_tree().await;
+
+    let pretty = Pretty::builder()
+        .uuid(false)
+        .timestamp(false)
+        .level(false)
+        .icon(false)
+        .durations(false)
+        .percentages(false)
+        .field_separator(", ")
+        .build();
+
+    assert_eq!(
+        &pretty.fmt(&tree)?,
+        "my_span answer: 42\n┕━ [info]: hello, user: \"admin\", ok: true\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hidden_fields_and_unaligned_columns() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::hello_tree().await;
+
+    let pretty = Pretty::builder()
+        .uuid(false)
+        .timestamp(false)
+        .durations(false)
+        .fields(false)
+        .align_columns(false)
+        .build();
+
+    assert_eq!(
+        &pretty.fmt(&tree)?,
+        "INFO my_span [ 100.00% ]\nINFO ┕━ ｉ [info]: hello\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_default_shows_everything() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::hello_tree().await;
+    let output = &Pretty::new().fmt(&tree)?;
+    let span = tree.span()?;
+
+    assert!(output.starts_with(&span.uuid().to_string()));
+    assert!(output.contains("INFO     my_span [ "));
+    assert!(output.contains("| 100.00% ] answer: 42\n"));
+    assert!(output.contains("ｉ [info]: hello | user: \"admin\" | ok: true\n"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_timestamp_modes() -> Result<(), Box<dyn std::error::Error>> {
+    use tracing_forest::printer::TimestampMode;
+
+    let tree = common::hello_tree().await;
+    let span = tree.span()?;
+    let event = span.nodes()[0].event()?;
+
+    let lines = |mode| -> Result<Vec<String>, std::fmt::Error> {
+        let pretty = Pretty::builder()
+            .uuid(false)
+            .level(false)
+            .timestamp_mode(mode)
+            .align_columns(false)
+            .build();
+        let output = &pretty.fmt(&tree)?;
+        Ok(output.lines().map(str::to_string).collect())
+    };
+
+    let custom = lines(TimestampMode::Custom("%Y-%m-%d".into()))?;
+    assert!(custom[0].starts_with(&span.timestamp().format("%Y-%m-%d my_span").to_string()));
+
+    let since_root = lines(TimestampMode::SinceRoot)?;
+    assert!(since_root[0].starts_with("+0.00ns my_span"));
+    let elapsed = (event.timestamp() - span.timestamp()).to_std()?;
+    assert!(since_root[1].starts_with('+'));
+    assert!(since_root[1].ends_with("hello | user: \"admin\" | ok: true"));
+    assert_eq!(elapsed.is_zero(), since_root[1].starts_with("+0.00ns"));
+
+    let rfc3339 = lines(TimestampMode::Rfc3339)?;
+    assert!(rfc3339[1].starts_with(&event.timestamp().to_rfc3339()));
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "invalid strftime pattern")]
+fn test_invalid_timestamp_pattern_panics_when_configured() {
+    use tracing_forest::printer::TimestampMode;
+
+    let _ = Pretty::builder().timestamp_mode(TimestampMode::Custom("%Q".into()));
+}
+
+#[tokio::test]
+async fn test_custom_timestamps_are_aligned() -> Result<(), Box<dyn std::error::Error>> {
+    use tracing_forest::printer::TimestampMode;
+
+    let tree = common::hello_tree().await;
+    let span = tree.span()?;
+
+    let pretty = Pretty::builder()
+        .uuid(false)
+        .timestamp_mode(TimestampMode::Custom("%A".into()))
+        .build();
+    let output = pretty.fmt(&tree)?;
+
+    // Weekdays are padded to the width of the longest one, "Wednesday".
+    let weekday = span.timestamp().format("%A").to_string();
+    assert!(output.starts_with(&format!("{weekday:<9} INFO     my_span")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_color_modes() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::hello_tree().await;
+
+    let never = Pretty::builder().color(ColorMode::Never).build();
+    assert!(!never.fmt_for_writer(&tree, true)?.contains('\x1b'));
+
+    // Output that isn't written to a terminal is never colored automatically.
+    let auto = Pretty::builder().color(ColorMode::Auto).build();
+    assert!(!auto.fmt(&tree)?.contains('\x1b'));
+    assert!(!auto.fmt_for_writer(&tree, false)?.contains('\x1b'));
+
+    let always = Pretty::builder().color(ColorMode::Always).build();
+    assert_eq!(
+        always.fmt_for_writer(&tree, false)?.contains('\x1b'),
+        cfg!(feature = "ansi")
+    );
+
+    Ok(())
+}
+
+/// A writer that says it's a terminal, and counts how often it's asked.
+#[derive(Clone, Default)]
+struct Terminal {
+    output: Arc<Mutex<Vec<u8>>>,
+    asked: Arc<AtomicUsize>,
+}
+
+impl Terminal {
+    fn output(&self) -> String {
+        String::from_utf8(self.output.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl io::Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl MakeWriter<'_> for Terminal {
+    type Writer = Terminal;
+
+    fn make_writer(&self) -> Terminal {
+        self.clone()
+    }
+}
+
+impl MakeTerminal for Terminal {
+    fn is_terminal(&self) -> bool {
+        self.asked.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+}
+
+#[tokio::test]
+async fn test_printer_asks_writer_once_if_it_is_a_terminal() {
+    let auto = Pretty::builder().color(ColorMode::Auto).build();
+    let terminal = Terminal::default();
+    let printer = Printer::new().formatter(auto).writer(terminal.clone());
+
+    printer.process(common::hello_tree().await).unwrap();
+    printer.process(common::hello_tree().await).unwrap();
+
+    assert_eq!(terminal.asked.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_printer_colors_terminal_output() {
+    let auto = Pretty::builder().color(ColorMode::Auto).build();
+    let terminal = Terminal::default();
+
+    // Setting `is_terminal` bypasses both the writer and the environment.
+    Printer::new()
+        .formatter(auto)
+        .writer(terminal.clone())
+        .is_terminal(|| true)
+        .process(common::hello_tree().await)
+        .unwrap();
+
+    assert_eq!(terminal.asked.load(Ordering::Relaxed), 0);
+    assert_eq!(terminal.output().contains('\x1b'), cfg!(feature = "ansi"));
+}
+
+#[tokio::test]
+async fn test_ascii_glyphs_and_text_icons() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::capture_tree(|| {
+        info_span!("outer").in_scope(|| {
+            info_span!("inner").in_scope(|| {
+                warn!("nested");
+            });
+            error!("last");
+        });
+    })
+    .await;
+
+    let pretty = Pretty::builder()
+        .uuid(false)
+        .timestamp(false)
+        .level(false)
+        .durations(false)
+        .percentages(false)
+        .glyphs(Glyphs::ASCII)
+        .icon_mode(IconMode::Text)
+        .build();
+
+    assert_eq!(
+        pretty.fmt(&tree)?,
+        "outer\n|-- inner\n|   `-- W [warn]: nested\n`-- E [error]: last\n"
+    );
+
+    let custom = Pretty::builder()
+        .uuid(false)
+        .timestamp(false)
+        .level(false)
+        .icon(false)
+        .durations(false)
+        .percentages(false)
+        .glyphs(Glyphs::new("  ", ": ", "+ ", "- "))
+        .build();
+
+    assert_eq!(
+        custom.fmt(&tree)?,
+        "outer\n+ inner\n: - [warn]: nested\n- [error]: last\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_collapsed_subtrees() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::capture_tree(|| {
+        info_span!("outer").in_scope(|| {
+            debug_span!("inner").in_scope(|| {
+                debug!("first");
+                trace!("second");
+            });
+            error!("last");
+        });
+    })
+    .await;
+
+    let builder = Pretty::builder()
+        .uuid(false)
+        .timestamp(false)
+        .level(false)
+        .icon(false)
+        .durations(false)
+        .percentages(false);
+
+    let shallow = builder.clone().max_depth(0).build();
+    assert_eq!(
+        shallow.fmt(&tree)?,
+        "outer\n┕━ ... 1 span, 3 events hidden\n"
+    );
+
+    let quiet = builder.clone().collapse_below_level(Level::INFO).build();
+    assert_eq!(
+        quiet.fmt(&tree)?,
+        "outer\n┝━ inner\n│  ┕━ ... 0 spans, 2 events hidden\n┕━ [error]: last\n"
+    );
+
+    // Collapsing only affects rendering.
+    assert_eq!(tree.span()?.nodes().len(), 2);
+
+    let fast = builder
+        .durations(true)
+        .collapse_below(Duration::MAX)
+        .build();
+    let output = fast.fmt(&tree)?;
+    assert_eq!(output.lines().count(), 2);
+    assert!(output
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("┕━ ... 1 span, 3 events hidden [ "));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_line_and_wrapped_events() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::capture_tree(|| {
+        info_span!("outer", query = %"SELECT *\nFROM users").in_scope(|| {
+            info!("SELECT *\nFROM users");
+            warn!("aaa bbb ccc ddd eee fff ggg hhh iii jjj");
+        });
+    })
+    .await;
+
+    let builder = Pretty::builder()
+        .uuid(false)
+        .timestamp(false)
+        .icon(false)
+        .durations(false)
+        .percentages(false);
+
+    assert_eq!(
+        builder.clone().build().fmt(&tree)?,
+        "INFO     outer query: SELECT *\n         \
+         FROM users\n\
+         INFO     ┝━ [info]: SELECT *\n         \
+         │  FROM users\n\
+         WARN     ┕━ [warn]: aaa bbb ccc ddd eee fff ggg hhh iii jjj\n"
+    );
+
+    let wrapped = builder.level(false).wrap(24).build();
+    assert_eq!(
+        wrapped.fmt(&tree)?,
+        "outer query: SELECT *\n\
+         FROM users\n\
+         ┝━ [info]: SELECT *\n\
+         │  FROM users\n\
+         ┕━ [warn]: aaa bbb ccc\n   \
+         ddd eee fff ggg hhh\n   \
+         iii jjj\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrap_counts_wide_characters() -> Result<(), Box<dyn std::error::Error>> {
+    let tree = common::capture_tree(|| {
+        warn!("日本語 日本語 日本語 日本語 日本語");
+    })
+    .await;
+
+    let wrapped = Pretty::builder()
+        .uuid(false)
+        .timestamp(false)
+        .level(false)
+        .icon(false)
+        .wrap(20)
+        .build();
+
+    // Each word takes up six columns.
+    assert_eq!(
+        wrapped.fmt(&tree)?,
+        "[warn]: 日本語\n日本語 日本語 日本語\n日本語\n"
+    );
+
+    Ok(())
+}
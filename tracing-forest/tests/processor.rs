@@ -0,0 +1,606 @@
+mod common;
+
+use common::{message, with_processor, Collect};
+use std::sync::{Arc, Mutex};
+use tracing_forest::processor::{self, Processor};
+use tracing_forest::tree::Tree;
+use tracing_forest::util::*;
+
+/// A processor that always fails.
+struct Fail;
+
+impl Processor for Fail {
+    fn process(&self, tree: Tree) -> processor::Result {
+        Err(processor::error(tree, "failed".into()))
+    }
+}
+
+#[test]
+fn test_tee_gives_tree_to_both() {
+    let first = Collect::default();
+    let second = Collect::default();
+
+    with_processor(first.clone().and(second.clone()), || {
+        info!("hello");
+        info_span!("my_span").in_scope(|| {});
+    });
+
+    assert_eq!(first.messages(), ["hello", "my_span"]);
+    assert_eq!(second.messages(), ["hello", "my_span"]);
+}
+
+#[test]
+fn test_tee_runs_second_when_first_fails() {
+    let second = Collect::default();
+    let fallback = Collect::default();
+
+    with_processor(Fail.and(second.clone()).or(fallback.clone()), || {
+        info!("hello");
+    });
+
+    assert_eq!(second.messages(), ["hello"]);
+    assert_eq!(fallback.messages(), ["hello"]);
+}
+
+#[test]
+fn test_tee_combines_errors() {
+    let tree = Tree::Event(capture_event());
+    let err = Fail.and(Fail).process(tree).unwrap_err();
+
+    assert_eq!(err.to_string(), "failed, and failed");
+}
+
+#[allow(clippy::result_large_err)]
+fn capture_event() -> tracing_forest::tree::Event {
+    let collected = Arc::new(Mutex::new(None));
+    let sink = collected.clone();
+
+    with_processor(
+        processor::from_fn(move |tree| {
+            *sink.lock().unwrap() = Some(tree);
+            Ok(())
+        }),
+        || info!("hello"),
+    );
+
+    let tree = collected.lock().unwrap().take().unwrap();
+    tree.event().unwrap().clone()
+}
+
+#[test]
+fn test_filter_drops_trees() {
+    let collect = Collect::default();
+    let fallback = Collect::default();
+
+    let processor = collect
+        .clone()
+        .filter(|tree: &Tree| tree.span().is_ok())
+        .or(fallback.clone());
+
+    with_processor(processor, || {
+        info!("dropped");
+        info_span!("kept").in_scope(|| {});
+    });
+
+    assert_eq!(collect.messages(), ["kept"]);
+    assert!(fallback.messages().is_empty());
+}
+
+#[test]
+fn test_map_and_filter_map() {
+    let collect = Collect::default();
+
+    let processor = collect
+        .clone()
+        .map(|tree| match tree {
+            Tree::Span(span) if !span.nodes().is_empty() => span.nodes()[0].clone(),
+            tree => tree,
+        })
+        .filter_map(|tree| match &tree {
+            Tree::Event(event) if event.message() == Some("skip") => None,
+            _ => Some(tree),
+        });
+
+    with_processor(processor, || {
+        info!("skip");
+        info_span!("outer").in_scope(|| info!("inner"));
+        info_span!("empty").in_scope(|| {});
+    });
+
+    assert_eq!(collect.messages(), ["inner", "empty"]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_filter_on_sender() -> Result<(), Box<dyn std::error::Error>> {
+    let logs = tracing_forest::capture()
+        .map_sender(|sender| sender.filter(|tree: &Tree| tree.event().is_ok()).or_none())
+        .build()
+        .on(async {
+            info!("kept");
+            info_span!("dropped").in_scope(|| {});
+        })
+        .await;
+
+    assert!(logs.len() == 1);
+    assert!(logs[0].event()?.message() == Some("kept"));
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_spawn_blocking_in_worker_task() {
+    let collect = Collect::default();
+    let processor = collect.clone();
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| processor.spawn_blocking())
+        .build()
+        .on(async {
+            info!("hello");
+            info!("world");
+        })
+        .await;
+
+    assert_eq!(collect.messages(), ["hello", "world"]);
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_spawn_blocking_returns_tree_when_cancelled() {
+    use tracing_forest::processor::AsyncProcessor;
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let handle = runtime.handle().clone();
+    runtime.shutdown_background();
+
+    let processor = Collect::default().spawn_blocking();
+    let tree = Tree::Event(capture_event());
+    let err = handle.block_on(processor.process_async(tree)).unwrap_err();
+
+    assert_eq!(err.tree.event().unwrap().message(), Some("hello"));
+}
+
+#[test]
+fn test_block_on_async_processor() {
+    use std::future::Future;
+    use tracing_forest::processor::AsyncProcessor;
+
+    struct Yielding(Collect);
+
+    impl AsyncProcessor for Yielding {
+        fn process_async(&self, tree: Tree) -> impl Future<Output = processor::Result> + Send {
+            let mut yielded = false;
+            let collect = self.0.clone();
+            let mut tree = Some(tree);
+            std::future::poll_fn(move |cx| {
+                if !yielded {
+                    yielded = true;
+                    cx.waker().wake_by_ref();
+                    return std::task::Poll::Pending;
+                }
+                std::task::Poll::Ready(collect.process(tree.take().unwrap()))
+            })
+        }
+    }
+
+    let collect = Collect::default();
+
+    with_processor(Yielding(collect.clone()).block_on(), || {
+        info!("hello");
+    });
+
+    assert_eq!(collect.messages(), ["hello"]);
+}
+
+/// A processor that fails a set number of times before succeeding.
+#[derive(Clone)]
+struct Flaky {
+    failures: Arc<Mutex<usize>>,
+    collect: Collect,
+}
+
+impl Flaky {
+    fn new(failures: usize) -> Self {
+        Flaky {
+            failures: Arc::new(Mutex::new(failures)),
+            collect: Collect::default(),
+        }
+    }
+}
+
+impl Processor for Flaky {
+    fn process(&self, tree: Tree) -> processor::Result {
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(processor::error(tree, "flaked".into()));
+        }
+        self.collect.process(tree)
+    }
+}
+
+#[test]
+fn test_retry_until_success() {
+    use std::time::Duration;
+    use tracing_forest::processor::retry::Policy;
+
+    let flaky = Flaky::new(2);
+    let fallback = Collect::default();
+    let policy = Policy::new(3).initial_backoff(Duration::from_millis(1));
+
+    with_processor(flaky.clone().retry(policy).or(fallback.clone()), || {
+        info!("hello");
+    });
+
+    assert_eq!(flaky.collect.messages(), ["hello"]);
+    assert!(fallback.messages().is_empty());
+}
+
+#[test]
+fn test_retry_falls_back_when_exhausted() {
+    use std::time::Duration;
+    use tracing_forest::processor::retry::Policy;
+
+    let flaky = Flaky::new(3);
+    let fallback = Collect::default();
+    let policy = Policy::new(3).initial_backoff(Duration::from_millis(1));
+
+    with_processor(flaky.clone().retry(policy).or(fallback.clone()), || {
+        info!("hello");
+    });
+
+    assert!(flaky.collect.messages().is_empty());
+    assert_eq!(fallback.messages(), ["hello"]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_retry_in_worker_task() {
+    use std::time::Duration;
+    use tracing_forest::processor::{retry::Policy, AsyncProcessor};
+
+    let flaky = Flaky::new(2);
+    let processor = flaky.clone();
+    let policy = Policy::new(3).initial_backoff(Duration::from_millis(1));
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| processor.retry_async(policy))
+        .build()
+        .on(async {
+            info!("hello");
+        })
+        .await;
+
+    assert_eq!(flaky.collect.messages(), ["hello"]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_retry_falls_back_when_exhausted() {
+    use std::time::Duration;
+    use tracing_forest::processor::{hook::Silent, retry::Policy, AsyncProcessor};
+
+    let flaky = Flaky::new(3);
+    let fallback = Collect::default();
+    let processor = flaky.clone();
+    let policy = Policy::new(3).initial_backoff(Duration::from_millis(1));
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| {
+            processor
+                .retry_async(policy)
+                .or_async(fallback.clone())
+                .hook(Silent)
+        })
+        .build()
+        .on(async {
+            info!("hello");
+        })
+        .await;
+
+    assert!(flaky.collect.messages().is_empty());
+    assert_eq!(fallback.messages(), ["hello"]);
+}
+
+#[test]
+fn test_rate_limit_suppresses_and_summarizes() {
+    use std::time::Duration;
+    use tracing_forest::processor::rate_limit::RateLimit;
+
+    let collect = Collect::default();
+    let overflow = Collect::default();
+
+    let processor = Arc::new(
+        RateLimit::new(collect.clone(), 1)
+            .burst(2)
+            .summary_interval(Duration::ZERO)
+            .overflow(overflow.clone()),
+    );
+
+    with_processor(processor.clone(), || {
+        for i in 0..5 {
+            info!("{}", i);
+        }
+    });
+
+    assert_eq!(collect.messages(), ["0", "1"]);
+    assert_eq!(overflow.messages(), ["2", "3", "4"]);
+
+    processor.flush().unwrap();
+    assert_eq!(
+        collect.messages(),
+        ["0", "1", "3 trees suppressed by rate limit"]
+    );
+}
+
+#[test]
+fn test_rate_limit_flush_respects_summary_interval() {
+    use std::time::Duration;
+    use tracing_forest::processor::rate_limit::RateLimit;
+
+    let collect = Collect::default();
+    let processor = Arc::new(
+        RateLimit::new(collect.clone(), 1)
+            .burst(1)
+            .summary_interval(Duration::from_millis(200)),
+    );
+    let summaries = || {
+        collect
+            .messages()
+            .iter()
+            .filter(|message| message.ends_with("suppressed by rate limit"))
+            .count()
+    };
+
+    with_processor(processor.clone(), || {
+        for i in 0..3 {
+            info!("{}", i);
+        }
+        processor.flush().unwrap();
+        assert_eq!(summaries(), 0);
+
+        std::thread::sleep(Duration::from_millis(250));
+        processor.flush().unwrap();
+        assert_eq!(summaries(), 1);
+
+        info!("suppressed");
+        processor.flush().unwrap();
+        processor.flush().unwrap();
+        assert_eq!(summaries(), 1);
+    });
+}
+
+#[test]
+fn test_rate_limit_by_root_name() {
+    use tracing_forest::processor::rate_limit::{Key, RateLimit};
+
+    let collect = Collect::default();
+    let processor = RateLimit::new(collect.clone(), 1).key(Key::RootName);
+
+    with_processor(processor, || {
+        info_span!("a").in_scope(|| {});
+        info_span!("a").in_scope(|| {});
+        info_span!("b").in_scope(|| {});
+    });
+
+    assert_eq!(collect.messages(), ["a", "b"]);
+}
+
+#[test]
+fn test_fallback_hooks() {
+    use tracing_forest::processor::hook::{Counter, Silent};
+
+    let counter = Counter::new();
+    let fallback = Collect::default();
+
+    with_processor(Fail.or(fallback.clone()).hook(counter.clone()), || {
+        info!("first");
+        info!("second");
+    });
+
+    assert_eq!(counter.count(), 2);
+    assert_eq!(fallback.messages(), ["first", "second"]);
+
+    let sources = Arc::new(Mutex::new(Vec::new()));
+    let hook = {
+        let sources = sources.clone();
+        move |err: &processor::Error| {
+            let source = std::error::Error::source(err).unwrap().to_string();
+            sources.lock().unwrap().push(source);
+        }
+    };
+
+    with_processor(Fail.or(Fail.or_none().hook(Silent)).hook(hook), || {
+        info!("hello");
+    });
+
+    assert_eq!(*sources.lock().unwrap(), ["failed"]);
+}
+
+/// A processor that records lifecycle calls alongside processed trees.
+#[derive(Clone, Default)]
+struct Lifecycle(Arc<Mutex<Vec<String>>>);
+
+impl Lifecycle {
+    fn calls(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Processor for Lifecycle {
+    fn process(&self, tree: Tree) -> processor::Result {
+        self.0.lock().unwrap().push(message(&tree));
+        Ok(())
+    }
+
+    fn flush(&self) -> processor::FlushResult {
+        self.0.lock().unwrap().push("flush".to_string());
+        Ok(())
+    }
+
+    fn shutdown(&self) -> processor::FlushResult {
+        self.0.lock().unwrap().push("shutdown".to_string());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_worker_task_flushes_then_shuts_down() {
+    let lifecycle = Lifecycle::default();
+    let processor = lifecycle.clone();
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| processor.or_none())
+        .build()
+        .on(async {
+            info!("hello");
+        })
+        .await;
+
+    assert_eq!(lifecycle.calls(), ["hello", "flush", "shutdown"]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_worker_task_shuts_down_when_the_future_panics() {
+    use std::time::Duration;
+
+    let lifecycle = Lifecycle::default();
+    let processor = lifecycle.clone();
+
+    let runtime = tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| processor.or_none())
+        .flush_interval(Duration::from_millis(10))
+        .build();
+    let result = tokio::spawn(runtime.on(async {
+        info!("hello");
+        panic!("the future panicked");
+    }))
+    .await;
+    assert!(result.unwrap_err().is_panic());
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while lifecycle.calls().last().map(String::as_str) != Some("shutdown") {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the worker task never shut down");
+    assert_eq!(lifecycle.calls()[0], "hello");
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn test_lifecycle_forwarded_through_wrappers() {
+    let lifecycle = Lifecycle::default();
+    let processor = Box::new(Arc::new(
+        lifecycle.clone().and(processor::from_fn(|_| Ok(()))),
+    ));
+
+    processor.flush().unwrap();
+    processor.shutdown().unwrap();
+
+    assert_eq!(lifecycle.calls(), ["flush", "shutdown"]);
+}
+
+/// A processor whose flush and shutdown always fail.
+struct FailLifecycle;
+
+impl Processor for FailLifecycle {
+    fn process(&self, _tree: Tree) -> processor::Result {
+        Ok(())
+    }
+
+    fn flush(&self) -> processor::FlushResult {
+        Err("flush failed".into())
+    }
+
+    fn shutdown(&self) -> processor::FlushResult {
+        Err("shutdown failed".into())
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_worker_task_reports_lifecycle_errors_to_hook() {
+    use tracing_forest::processor::hook::Counter;
+
+    let counter = Counter::new();
+
+    tracing_forest::worker_task()
+        .set_global(false)
+        .map_receiver(|_printer| FailLifecycle)
+        .hook(counter.clone())
+        .build()
+        .on(async {
+            info!("hello");
+        })
+        .await;
+
+    assert_eq!(counter.count(), 2);
+}
+
+#[test]
+fn test_flush_guard_reports_lifecycle_errors_to_hook() {
+    use tracing_forest::processor::hook::Counter;
+
+    let counter = Counter::new();
+    let (_processor, guard) = FailLifecycle.flush_guard();
+    drop(guard.hook(counter.clone()));
+
+    assert_eq!(counter.count(), 2);
+}
+
+#[test]
+fn test_ring_buffer_keeps_most_recent() {
+    use tracing_forest::processor::ring::RingBuffer;
+
+    let ring = RingBuffer::new(2);
+    let handle = ring.handle();
+
+    with_processor(ring, || {
+        info!("first");
+        info!("second");
+        info!("third");
+    });
+
+    let snapshot: Vec<_> = handle
+        .snapshot()
+        .iter()
+        .map(|tree| tree.event().unwrap().message().unwrap().to_string())
+        .collect();
+    assert_eq!(snapshot, ["second", "third"]);
+
+    let collect = Collect::default();
+    handle.dump(&collect).unwrap();
+
+    assert_eq!(collect.messages(), ["second", "third"]);
+    assert!(handle.is_empty());
+}
+
+#[test]
+fn test_ring_buffer_requeues_on_failed_dump() {
+    use std::time::Duration;
+    use tracing_forest::processor::ring::RingBuffer;
+
+    let ring = RingBuffer::new(8).max_age(Duration::from_secs(60));
+    let handle = ring.handle();
+
+    with_processor(ring, || {
+        info!("first");
+        info!("second");
+    });
+
+    let err = handle.dump(&Fail).unwrap_err();
+
+    assert_eq!(err.tree.event().unwrap().message(), Some("first"));
+    assert_eq!(handle.len(), 1);
+}
@@ -0,0 +1,233 @@
+mod common;
+
+use common::Collect;
+use tracing_forest::processor::router::{Router, Rule};
+use tracing_forest::tree::Tree;
+use tracing_forest::{util::*, ForestLayer, Tag};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+/// Tags events by their target, with `critical` as the suffix of errors.
+fn tag(event: &tracing::Event) -> Option<Tag> {
+    let level = *event.metadata().level();
+    match event.metadata().target() {
+        "security" if level == Level::ERROR => Some(
+            Tag::builder()
+                .prefix("security")
+                .suffix("critical")
+                .icon('🔐')
+                .build(),
+        ),
+        "security" => Some(Tag::builder().prefix("security").level(level).build()),
+        "bare" => Some(Tag::builder().suffix("bare").icon('*').build()),
+        _ => None,
+    }
+}
+
+/// Runs `f` with a subscriber that routes trees through `router`.
+fn with_router<D>(router: Router<D>, f: impl FnOnce())
+where
+    D: tracing_forest::Processor + Send + Sync,
+{
+    let subscriber = Registry::default().with(ForestLayer::new(router, tag));
+    tracing::subscriber::with_default(subscriber, f);
+}
+
+#[test]
+fn test_router_uses_first_matching_rule() {
+    let audit = Collect::default();
+    let alerts = Collect::default();
+    let db = Collect::default();
+    let admin = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(Rule::tag_prefix("security"), audit.clone())
+        .route(Rule::level(Level::ERROR), alerts.clone())
+        .route(Rule::name("db"), db.clone())
+        .route(Rule::field("user", "admin"), admin.clone())
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        info!(target: "security", "login");
+        error!(target: "security", "breach");
+        info_span!("db").in_scope(|| error!("query failed"));
+        info_span!("db").in_scope(|| info!("query"));
+        info!(user = "admin", "hello");
+        info!(user = %"admin", "display");
+        info!(user = "admin\"", "other");
+    });
+
+    assert_eq!(audit.messages(), ["login", "breach"]);
+    assert_eq!(alerts.messages(), ["db"]);
+    assert_eq!(db.messages(), ["db"]);
+    assert_eq!(admin.messages(), ["hello", "display"]);
+    assert_eq!(rest.messages(), ["other"]);
+}
+
+#[test]
+fn test_router_drops_unmatched_without_default_route() {
+    let matched = Collect::default();
+
+    let router = Router::new().route(Rule::name("kept"), matched.clone());
+
+    with_router(router, || {
+        info_span!("kept").in_scope(|| {});
+        info_span!("dropped").in_scope(|| {});
+        info!("dropped");
+    });
+
+    assert_eq!(matched.messages(), ["kept"]);
+}
+
+#[test]
+fn test_rule_name_only_matches_root_span() {
+    let matched = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(Rule::name("db"), matched.clone())
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        info_span!("db").in_scope(|| {});
+        info_span!("request").in_scope(|| info_span!("db").in_scope(|| {}));
+        info!("db");
+    });
+
+    assert_eq!(matched.messages(), ["db"]);
+    assert_eq!(rest.messages(), ["request", "db"]);
+}
+
+#[test]
+fn test_rule_level_matches_nested_nodes() {
+    let matched = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(Rule::level(Level::WARN), matched.clone())
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        warn!("warn");
+        error!("error");
+        info!("info");
+        info_span!("outer").in_scope(|| info_span!("inner").in_scope(|| warn!("nested")));
+        warn_span!("span").in_scope(|| {});
+    });
+
+    assert_eq!(matched.messages(), ["warn", "error", "outer", "span"]);
+    assert_eq!(rest.messages(), ["info"]);
+}
+
+#[test]
+fn test_rule_tag_matches_exact_tag() {
+    let critical = Collect::default();
+    let bare = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(Rule::tag("security.critical"), critical.clone())
+        .route(Rule::tag("bare"), bare.clone())
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        error!(target: "security", "breach");
+        info!(target: "security", "login");
+        info!(target: "bare", "bare");
+        info_span!("request").in_scope(|| error!(target: "security", "nested"));
+        info!("untagged");
+    });
+
+    assert_eq!(critical.messages(), ["breach", "request"]);
+    assert_eq!(bare.messages(), ["bare"]);
+    assert_eq!(rest.messages(), ["login", "untagged"]);
+}
+
+#[test]
+fn test_rule_tag_does_not_match_partial_tag() {
+    let matched = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(Rule::tag("security"), matched.clone())
+        .route(Rule::tag("critical"), matched.clone())
+        .route(Rule::tag("security.crit"), matched.clone())
+        .route(Rule::tag("securitycritical"), matched.clone())
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        error!(target: "security", "breach");
+    });
+
+    assert!(matched.messages().is_empty());
+    assert_eq!(rest.messages(), ["breach"]);
+}
+
+#[test]
+fn test_rule_tag_prefix_matches_any_suffix() {
+    let matched = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(Rule::tag_prefix("security"), matched.clone())
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        error!(target: "security", "breach");
+        info!(target: "security", "login");
+        info!(target: "bare", "bare");
+    });
+
+    assert_eq!(matched.messages(), ["breach", "login"]);
+    assert_eq!(rest.messages(), ["bare"]);
+}
+
+#[test]
+fn test_rule_field_matches_raw_and_quoted_values() {
+    let raw = Collect::default();
+    let quoted = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(Rule::field("user", "admin"), raw.clone())
+        .route(Rule::field("id", "\"42\""), quoted.clone())
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        info!(user = "admin", "debug");
+        info!(user = %"admin", "display");
+        info!(id = "42", "quoted string");
+        info!(id = 42, "number");
+        info!(user = "root", "other user");
+        info_span!("span", user = "admin").in_scope(|| {});
+        info_span!("outer").in_scope(|| info!(user = "admin", "nested"));
+    });
+
+    assert_eq!(raw.messages(), ["debug", "display", "span", "outer"]);
+    assert_eq!(quoted.messages(), ["quoted string"]);
+    assert_eq!(rest.messages(), ["number", "other user"]);
+}
+
+#[test]
+fn test_rule_custom() {
+    let matched = Collect::default();
+    let rest = Collect::default();
+
+    let router = Router::new()
+        .route(
+            Rule::custom(|tree| matches!(tree, Tree::Span(span) if span.nodes().len() > 1)),
+            matched.clone(),
+        )
+        .default_route(rest.clone());
+
+    with_router(router, || {
+        info_span!("busy").in_scope(|| {
+            info!("first");
+            info!("second");
+        });
+        info_span!("idle").in_scope(|| info!("only"));
+    });
+
+    assert_eq!(matched.messages(), ["busy"]);
+    assert_eq!(rest.messages(), ["idle"]);
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::with_processor;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing_forest::processor::socket::{Reader, Socket};
+use tracing_forest::processor::{self, Processor};
+use tracing_forest::tree::Tree;
+use tracing_forest::{util::*, ForestLayer, Tag};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+fn receive(listener: TcpListener) -> thread::JoinHandle<Vec<Tree>> {
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        Reader::new(stream).map(Result::unwrap).collect()
+    })
+}
+
+#[test]
+fn test_tcp_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let socket = Socket::tcp(listener.local_addr()?)?;
+    let receiver = receive(listener);
+
+    let tag = |event: &tracing::Event| match event.metadata().target() {
+        "security" => Some(Tag::builder().prefix("security").level(Level::WARN).build()),
+        _ => None,
+    };
+
+    let subscriber = Registry::default().with(ForestLayer::new(socket, tag));
+    tracing::subscriber::with_default(subscriber, || {
+        info!(answer = 42, "hello");
+        info_span!("my_span", key = "value").in_scope(|| {
+            warn!(target: "security", "nested");
+        });
+    });
+
+    let trees = receiver.join().unwrap();
+    assert_eq!(trees.len(), 2);
+
+    let event = trees[0].event()?;
+    assert_eq!(event.message(), Some("hello"));
+    assert_eq!(event.level(), Level::INFO);
+    assert_eq!(event.fields()[0].key(), "answer");
+    assert_eq!(event.fields()[0].value(), "42");
+    assert_eq!(event.tag(), None);
+
+    let span = trees[1].span()?;
+    assert_eq!(span.name(), "my_span");
+    assert_eq!(span.fields()[0].value(), "\"value\"");
+
+    let nested = span.nodes()[0].event()?;
+    assert_eq!(nested.message(), Some("nested"));
+
+    let tag = nested.tag().unwrap();
+    assert_eq!(tag.prefix(), Some("security"));
+    assert_eq!(tag.suffix(), "warn");
+    assert_eq!(tag.icon(), Tag::from(Level::WARN).icon());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::net::UnixListener;
+
+    let path = std::env::temp_dir().join(format!("tracing-forest-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    let socket = Socket::unix(&path);
+
+    let receiver = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        Reader::new(stream).map(Result::unwrap).collect::<Vec<_>>()
+    });
+
+    with_processor(socket, || info!("over unix"));
+
+    let trees = receiver.join().unwrap();
+    assert_eq!(trees[0].event()?.message(), Some("over unix"));
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn test_reconnects_after_failure() -> Result<(), Box<dyn std::error::Error>> {
+    let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    let socket = Socket::tcp(addr)?.reconnect_delay(Duration::ZERO);
+
+    let failed = Arc::new(Mutex::new(0));
+    let counter = failed.clone();
+    let processor = socket.or(processor::from_fn(move |_| {
+        *counter.lock().unwrap() += 1;
+        Ok(())
+    }));
+    let processor = processor.hook(processor::hook::Silent);
+
+    let subscriber = Registry::default().with(ForestLayer::from(processor));
+    let receiver = tracing::subscriber::with_default(subscriber, || {
+        info!("nobody is listening");
+
+        let receiver = receive(TcpListener::bind(addr).unwrap());
+        info!("somebody is listening");
+        receiver
+    });
+
+    let trees = receiver.join().unwrap();
+    assert_eq!(trees.len(), 1);
+    assert_eq!(trees[0].event()?.message(), Some("somebody is listening"));
+    assert_eq!(*failed.lock().unwrap(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_reader_rejects_long_lines() {
+    let input = format!("\n{}\n", "x".repeat(100));
+    let mut reader = Reader::new(input.as_bytes()).max_line_length(10);
+
+    let err = reader.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(reader.next().is_none());
+}
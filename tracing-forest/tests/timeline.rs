@@ -0,0 +1,64 @@
+#![cfg(all(feature = "tokio", feature = "timeline"))]
+use std::error::Error;
+use tokio::task::yield_now;
+use tracing_forest::{traits::*, util::*};
+
+#[tokio::test]
+async fn test_intervals_per_poll() -> Result<(), Box<dyn Error>> {
+    let logs = tracing_forest::capture()
+        .build()
+        .on(async {
+            async {
+                for _ in 0..3 {
+                    yield_now().await;
+                }
+            }
+            .instrument(info_span!("polled"))
+            .await;
+        })
+        .await;
+
+    assert!(logs.len() == 1);
+
+    let polled = logs[0].span()?;
+    // `Instrumented` may also enter the span to drop the inner future.
+    assert!(polled.enter_count() >= 4);
+    assert!(polled.intervals().len() == polled.enter_count());
+
+    let total: std::time::Duration = polled.intervals().iter().map(|i| i.duration()).sum();
+    assert!(total == polled.total_duration());
+
+    for pair in polled.intervals().windows(2) {
+        assert!(pair[0].end() <= pair[1].start());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_intervals_share_root_origin() -> Result<(), Box<dyn Error>> {
+    let logs = tracing_forest::capture()
+        .build()
+        .on(async {
+            info_span!("parent").in_scope(|| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                info_span!("child").in_scope(|| {});
+            });
+        })
+        .await;
+
+    let parent = logs[0].span()?;
+    let child = parent.nodes()[0].span()?;
+
+    assert!(parent.intervals().len() == 1);
+    assert!(child.intervals().len() == 1);
+
+    let parent_interval = parent.intervals()[0];
+    let child_interval = child.intervals()[0];
+
+    assert!(child_interval.start() >= std::time::Duration::from_millis(10));
+    assert!(parent_interval.start() <= child_interval.start());
+    assert!(child_interval.end() <= parent_interval.end());
+
+    Ok(())
+}